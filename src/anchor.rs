@@ -0,0 +1,256 @@
+use std::{collections::HashMap, hash::Hash};
+
+use crate::delta::Delta;
+
+/// Side an [Anchor] sticks to when text is inserted at its position
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Gravity {
+    /// Sticks to the character before it: text inserted at the anchor goes after it
+    Left,
+    /// Sticks to the character after it: text inserted at the anchor goes before it
+    Right,
+}
+
+/// A position in a document that follows the edits made to it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Anchor {
+    pub position: usize,
+    pub gravity: Gravity,
+}
+
+impl Anchor {
+    pub fn new(position: usize, gravity: Gravity) -> Self {
+        Anchor { position, gravity }
+    }
+
+    /// Whether the character the anchor sticks to is in the deleted range
+    fn is_deleted_by(&self, start: usize, end: usize) -> bool {
+        match self.gravity {
+            Gravity::Left => start < self.position && self.position <= end,
+            Gravity::Right => start <= self.position && self.position < end,
+        }
+    }
+}
+
+/// Set of [Anchor]s identified by key, updated together by change [Delta]s
+///
+/// An anchor is deleted when the character it sticks to is deleted; it then collapses to
+/// the position of the deletion and stays in the set until removed.
+///
+/// # Example
+///
+/// ```
+/// use quill_delta_rs::{AnchorSet, Delta, Gravity};
+///
+/// let mut anchors = AnchorSet::new();
+/// anchors.insert("start", 6, Gravity::Right);
+/// anchors.insert("end", 11, Gravity::Left);
+///
+/// let deleted = anchors.apply(&Delta::new().insert("Hi ", None).retain(11, None).insert("!", None));
+/// assert!(deleted.is_empty());
+/// assert_eq!(Some(9), anchors.position(&"start"));
+/// assert_eq!(Some(14), anchors.position(&"end"));
+///
+/// let deleted = anchors.apply(&Delta::new().retain(8, None).delete(3));
+/// assert_eq!(vec!["start"], deleted);
+/// assert_eq!(Some(8), anchors.position(&"start"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct AnchorSet<K> {
+    anchors: HashMap<K, Anchor>,
+}
+
+impl<K> Default for AnchorSet<K> {
+    fn default() -> Self {
+        AnchorSet {
+            anchors: HashMap::new(),
+        }
+    }
+}
+
+impl<K: Eq + Hash + Clone> AnchorSet<K> {
+    /// Create an empty [AnchorSet].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an anchor, returning the previous anchor of the key if any
+    pub fn insert(&mut self, key: K, position: usize, gravity: Gravity) -> Option<Anchor> {
+        self.anchors.insert(key, Anchor::new(position, gravity))
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<Anchor> {
+        self.anchors.remove(key)
+    }
+
+    pub fn get(&self, key: &K) -> Option<&Anchor> {
+        self.anchors.get(key)
+    }
+
+    pub fn position(&self, key: &K) -> Option<usize> {
+        self.anchors.get(key).map(|anchor| anchor.position)
+    }
+
+    pub fn len(&self) -> usize {
+        self.anchors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.anchors.is_empty()
+    }
+
+    pub fn iter(&self) -> impl std::iter::Iterator<Item = (&K, &Anchor)> {
+        self.anchors.iter()
+    }
+
+    /// Move every anchor through a change
+    ///
+    /// Anchors are sorted once and updated in a single pass over the operations of `change`.
+    /// Returns the keys of the anchors deleted by the change, in document order.
+    pub fn apply(&mut self, change: &Delta) -> Vec<K> {
+        let mut anchors: Vec<(&K, &mut Anchor)> = self.anchors.iter_mut().collect();
+        anchors.sort_by_key(|(_, anchor)| (anchor.position, anchor.gravity));
+
+        let mut deleted = Vec::new();
+        let mut pending = anchors.iter_mut().peekable();
+        // Offsets of the current operation in the document before and after the change
+        let mut old = 0;
+        let mut new = 0;
+        for op in change.ops() {
+            let length = op.len();
+            if op.is_insert() {
+                while let Some((_, anchor)) =
+                    pending.next_if(|(_, a)| a.position == old && a.gravity == Gravity::Left)
+                {
+                    anchor.position = new;
+                }
+                new += length;
+            } else if op.is_delete() {
+                let end = old + length;
+                while let Some((key, anchor)) = pending.next_if(|(_, a)| {
+                    a.position < end || (a.position == end && a.gravity == Gravity::Left)
+                }) {
+                    if anchor.is_deleted_by(old, end) {
+                        deleted.push((*key).clone());
+                    }
+                    anchor.position = new;
+                }
+                old = end;
+            } else {
+                while let Some((_, anchor)) = pending.next_if(|(_, a)| a.position < old + length) {
+                    anchor.position = new + anchor.position - old;
+                }
+                old += length;
+                new += length;
+            }
+        }
+        for (_, anchor) in pending {
+            anchor.position = new + anchor.position - old;
+        }
+        deleted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Delta;
+
+    use super::{AnchorSet, Gravity};
+
+    fn anchors(positions: &[(usize, Gravity)]) -> AnchorSet<usize> {
+        let mut anchors = AnchorSet::new();
+        for (key, (position, gravity)) in positions.iter().enumerate() {
+            anchors.insert(key, *position, *gravity);
+        }
+        anchors
+    }
+
+    fn positions(anchors: &AnchorSet<usize>) -> Vec<usize> {
+        (0..anchors.len())
+            .map(|key| anchors.position(&key).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn insert_before_and_after() {
+        let mut set = anchors(&[(2, Gravity::Left), (5, Gravity::Right)]);
+        let deleted = set.apply(
+            &Delta::new()
+                .insert("ab", None)
+                .retain(6, None)
+                .insert("c", None),
+        );
+        assert!(deleted.is_empty());
+        assert_eq!(vec![4, 7], positions(&set));
+    }
+
+    #[test]
+    fn insert_at_anchor() {
+        let mut set = anchors(&[(3, Gravity::Left), (3, Gravity::Right)]);
+        set.apply(&Delta::new().retain(3, None).insert("ab", None));
+        assert_eq!(vec![3, 5], positions(&set));
+    }
+
+    #[test]
+    fn insert_at_start() {
+        let mut set = anchors(&[(0, Gravity::Left), (0, Gravity::Right)]);
+        set.apply(&Delta::new().insert("ab", None));
+        assert_eq!(vec![0, 2], positions(&set));
+    }
+
+    #[test]
+    fn delete_around() {
+        let mut set = anchors(&[
+            (1, Gravity::Right),
+            (2, Gravity::Left),
+            (2, Gravity::Right),
+            (3, Gravity::Left),
+            (4, Gravity::Left),
+            (4, Gravity::Right),
+            (5, Gravity::Left),
+        ]);
+        let mut deleted = set.apply(&Delta::new().retain(2, None).delete(2));
+        deleted.sort();
+        assert_eq!(vec![2, 3, 4], deleted);
+        assert_eq!(vec![1, 2, 2, 2, 2, 2, 3], positions(&set));
+    }
+
+    #[test]
+    fn replace() {
+        let mut set = anchors(&[(2, Gravity::Left), (2, Gravity::Right), (4, Gravity::Right)]);
+        let deleted = set.apply(&Delta::new().retain(2, None).insert("xyz", None).delete(2));
+        assert_eq!(vec![1], deleted);
+        assert_eq!(vec![2, 5, 5], positions(&set));
+    }
+
+    #[test]
+    fn matches_transform_position() {
+        let change = Delta::new()
+            .retain(1, None)
+            .insert("ab", None)
+            .retain(2, None)
+            .delete(3)
+            .insert("c", None)
+            .retain(1, None)
+            .delete(1);
+        for position in 0..10 {
+            for (gravity, priority) in [(Gravity::Left, true), (Gravity::Right, false)] {
+                let mut set = anchors(&[(position, gravity)]);
+                set.apply(&change);
+                assert_eq!(
+                    change.transform_position(position, priority),
+                    set.position(&0).unwrap(),
+                    "position {position} {gravity:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn remove() {
+        let mut set = anchors(&[(2, Gravity::Left)]);
+        assert!(set.remove(&0).is_some());
+        assert!(set.is_empty());
+        assert!(set.apply(&Delta::new().delete(4)).is_empty());
+    }
+}
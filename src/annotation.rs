@@ -0,0 +1,293 @@
+use std::collections::{BTreeSet, HashMap};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    anchor::{AnchorSet, Gravity},
+    attributes::AttributesMap,
+    delta::Delta,
+    range::Range,
+};
+
+/// A comment or other note attached to a range of a document
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Annotation<T> {
+    pub range: Range,
+    pub author: String,
+    pub payload: T,
+}
+
+impl<T> Annotation<T> {
+    pub fn new<A: Into<String>>(range: Range, author: A, payload: T) -> Self {
+        Annotation {
+            range,
+            author: author.into(),
+            payload,
+        }
+    }
+}
+
+/// Store of [Annotation]s by id, kept apart from the document they annotate
+///
+/// Ranges follow the document through [Annotations::apply]: text inserted at either edge
+/// of a range is left outside of it. An annotation is orphaned, and removed from the store,
+/// when all of its text is deleted.
+///
+/// # Example
+///
+/// ```
+/// use quill_delta_rs::{Annotation, Annotations, Delta, Range};
+///
+/// let mut annotations = Annotations::new();
+/// annotations.insert("c1", Annotation::new(Range::new(6, 5), "alice", "Typo?"));
+/// annotations.insert("c2", Annotation::new(Range::new(0, 5), "bob", "Nice"));
+///
+/// let orphans = annotations.apply(&Delta::new().insert("Hi ", None).delete(5));
+/// assert_eq!(vec!["c2".to_string()], orphans.into_iter().map(|(id, _)| id).collect::<Vec<_>>());
+/// assert_eq!(Range::new(4, 5), annotations.get("c1").unwrap().range);
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct Annotations<T> {
+    annotations: HashMap<String, Annotation<T>>,
+}
+
+impl<T> Default for Annotations<T> {
+    fn default() -> Self {
+        Annotations {
+            annotations: HashMap::new(),
+        }
+    }
+}
+
+impl<T> Annotations<T> {
+    /// Create an empty [Annotations] store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an annotation, returning the previous annotation with the same id if any
+    pub fn insert<K: Into<String>>(
+        &mut self,
+        id: K,
+        annotation: Annotation<T>,
+    ) -> Option<Annotation<T>> {
+        self.annotations.insert(id.into(), annotation)
+    }
+
+    pub fn remove(&mut self, id: &str) -> Option<Annotation<T>> {
+        self.annotations.remove(id)
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Annotation<T>> {
+        self.annotations.get(id)
+    }
+
+    pub fn get_mut(&mut self, id: &str) -> Option<&mut Annotation<T>> {
+        self.annotations.get_mut(id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.annotations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.annotations.is_empty()
+    }
+
+    pub fn iter(&self) -> impl std::iter::Iterator<Item = (&String, &Annotation<T>)> {
+        self.annotations.iter()
+    }
+
+    /// Update every range through a change
+    ///
+    /// Returns the orphaned annotations, whose text was entirely deleted by the change,
+    /// after removing them from the store.
+    pub fn apply(&mut self, change: &Delta) -> Vec<(String, Annotation<T>)> {
+        let mut anchors = AnchorSet::new();
+        for (id, annotation) in &self.annotations {
+            let range = annotation.range;
+            anchors.insert((id.clone(), false), range.index, Gravity::Right);
+            if !range.is_collapsed() {
+                anchors.insert((id.clone(), true), range.end(), Gravity::Left);
+            }
+        }
+        anchors.apply(change);
+
+        let mut orphans = Vec::new();
+        for (id, annotation) in &mut self.annotations {
+            let index = anchors.position(&(id.clone(), false)).unwrap();
+            let end = anchors.position(&(id.clone(), true)).unwrap_or(index);
+            if !annotation.range.is_collapsed() && end <= index {
+                orphans.push(id.clone());
+            }
+            annotation.range = Range::new(index, end.saturating_sub(index));
+        }
+        orphans.sort();
+        orphans
+            .into_iter()
+            .map(|id| {
+                let annotation = self.annotations.remove(&id).unwrap();
+                (id, annotation)
+            })
+            .collect()
+    }
+
+    /// Export the annotated ranges as a change of attribute retains
+    ///
+    /// Each annotated character gets the attribute `key` set to the sorted array of the ids
+    /// of the annotations covering it, so composing the result onto the document
+    /// highlights them. Collapsed ranges are not exported.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use quill_delta_rs::{Annotation, Annotations, Delta, Range, {attributes, AttributesMap}};
+    /// use serde_json::json;
+    ///
+    /// let mut annotations = Annotations::new();
+    /// annotations.insert("c1", Annotation::new(Range::new(2, 4), "alice", ()));
+    /// annotations.insert("c2", Annotation::new(Range::new(4, 4), "bob", ()));
+    /// assert_eq!(
+    ///     Delta::new()
+    ///         .retain(2, None)
+    ///         .retain(2, Some(attributes!("comment" => json!(["c1"]))))
+    ///         .retain(2, Some(attributes!("comment" => json!(["c1", "c2"]))))
+    ///         .retain(2, Some(attributes!("comment" => json!(["c2"])))),
+    ///     annotations.to_delta("comment")
+    /// );
+    /// ```
+    pub fn to_delta(&self, key: &str) -> Delta {
+        let mut bounds: Vec<(usize, bool, &str)> = self
+            .annotations
+            .iter()
+            .filter(|(_, annotation)| !annotation.range.is_collapsed())
+            .flat_map(|(id, annotation)| {
+                [
+                    (annotation.range.index, true, id.as_str()),
+                    (annotation.range.end(), false, id.as_str()),
+                ]
+            })
+            .collect();
+        bounds.sort();
+
+        let mut delta = Delta::new();
+        let mut active = BTreeSet::new();
+        let mut offset = 0;
+        for (position, starts, id) in bounds {
+            if position > offset {
+                let attributes = (!active.is_empty()).then(|| {
+                    let ids = active.iter().map(|id: &&str| Value::from(*id)).collect();
+                    let mut attributes = AttributesMap::new();
+                    attributes.insert(key, Value::Array(ids));
+                    attributes
+                });
+                delta = delta.retain(position - offset, attributes);
+                offset = position;
+            }
+            if starts {
+                active.insert(id);
+            } else {
+                active.remove(id);
+            }
+        }
+        delta
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{AttributesMap, Delta, Range};
+
+    use super::{Annotation, Annotations};
+
+    fn store(ranges: &[(&str, usize, usize)]) -> Annotations<()> {
+        let mut annotations = Annotations::new();
+        for (id, index, length) in ranges {
+            annotations.insert(
+                *id,
+                Annotation::new(Range::new(*index, *length), "alice", ()),
+            );
+        }
+        annotations
+    }
+
+    fn range(annotations: &Annotations<()>, id: &str) -> Range {
+        annotations.get(id).unwrap().range
+    }
+
+    #[test]
+    fn insert_at_edges_stays_outside() {
+        let mut annotations = store(&[("c1", 2, 3)]);
+        annotations.apply(&Delta::new().retain(2, None).insert("ab", None));
+        assert_eq!(Range::new(4, 3), range(&annotations, "c1"));
+        annotations.apply(&Delta::new().retain(7, None).insert("ab", None));
+        assert_eq!(Range::new(4, 3), range(&annotations, "c1"));
+    }
+
+    #[test]
+    fn insert_inside_grows() {
+        let mut annotations = store(&[("c1", 2, 3)]);
+        annotations.apply(&Delta::new().retain(3, None).insert("ab", None));
+        assert_eq!(Range::new(2, 5), range(&annotations, "c1"));
+    }
+
+    #[test]
+    fn delete_part() {
+        let mut annotations = store(&[("c1", 2, 3)]);
+        let orphans = annotations.apply(&Delta::new().retain(1, None).delete(2));
+        assert!(orphans.is_empty());
+        assert_eq!(Range::new(1, 2), range(&annotations, "c1"));
+    }
+
+    #[test]
+    fn delete_all_orphans() {
+        let mut annotations = store(&[("c1", 2, 3), ("c2", 3, 1), ("c3", 6, 2)]);
+        let orphans = annotations.apply(&Delta::new().retain(1, None).delete(5));
+        assert_eq!(
+            vec!["c1", "c2"],
+            orphans
+                .iter()
+                .map(|(id, _)| id.as_str())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(Range::new(1, 0), orphans[0].1.range);
+        assert_eq!(1, annotations.len());
+        assert_eq!(Range::new(1, 2), range(&annotations, "c3"));
+    }
+
+    #[test]
+    fn collapsed() {
+        let mut annotations = store(&[("c1", 2, 0)]);
+        assert!(annotations.apply(&Delta::new().delete(4)).is_empty());
+        assert_eq!(Range::new(0, 0), range(&annotations, "c1"));
+    }
+
+    #[test]
+    fn to_delta() {
+        let annotations = store(&[("c1", 1, 2), ("c2", 5, 1), ("c3", 6, 0)]);
+        assert_eq!(
+            Delta::new()
+                .retain(1, None)
+                .retain(2, Some(attributes!("comment" => json!(["c1"]))))
+                .retain(2, None)
+                .retain(1, Some(attributes!("comment" => json!(["c2"])))),
+            annotations.to_delta("comment")
+        );
+        assert_eq!(Delta::new(), Annotations::<()>::new().to_delta("comment"));
+    }
+
+    #[test]
+    fn serde() {
+        let annotations = store(&[("c1", 1, 2)]);
+        let value = serde_json::to_value(&annotations).unwrap();
+        assert_eq!(
+            json!({"c1": {"range": {"index": 1, "length": 2}, "author": "alice", "payload": null}}),
+            value
+        );
+        assert_eq!(annotations, serde_json::from_value(value).unwrap());
+    }
+}
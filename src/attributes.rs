@@ -7,6 +7,8 @@ use std::{
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use crate::format::Format;
+
 #[macro_export]
 macro_rules! attributes {
     ($($k:expr => $v:expr),* $(,)?) => {
@@ -34,8 +36,8 @@ impl AttributesMap {
 
     /// Union of attributes, where conflicts are overriden by second argument
     ///
-    /// Always keeps [Value::Null] of `a`
-    /// If `keep_null` is `true`, [Value::Null] of `b` are kept
+    /// If `keep_null` is `true`, [Value::Null] of `a` and `b` are kept, so that composing
+    /// 2 retains preserves the formats they remove
     ///
     /// # Example
     ///
@@ -73,7 +75,7 @@ impl AttributesMap {
         }
 
         for key in a.0.keys() {
-            if !matches!(a[key], Value::Null) && !b.0.contains_key(key) {
+            if (keep_null || !matches!(a[key], Value::Null)) && !b.0.contains_key(key) {
                 attributes.0.insert(key.clone(), a[key].clone());
             }
         }
@@ -220,6 +222,32 @@ impl AttributesMap {
         }
     }
 
+    /// Intersection of 2 maps: the key-values present with the same value in both
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use quill_delta_rs::{attributes, AttributesMap};
+    ///
+    /// let a = attributes!("bold" => true, "color" => "red", "font" => "serif");
+    /// let b = attributes!("bold" => true, "color" => "blue");
+    /// assert_eq!(
+    ///     Some(attributes!("bold" => true)),
+    ///     AttributesMap::intersect(a, b)
+    /// );
+    /// ```
+    pub fn intersect(a: AttributesMap, b: AttributesMap) -> Option<AttributesMap> {
+        let attributes: AttributesMap = a
+            .into_iter()
+            .filter(|(key, value)| b.0.get(key) == Some(value))
+            .collect();
+        if attributes.is_empty() {
+            None
+        } else {
+            Some(attributes)
+        }
+    }
+
     /// Returns `true` if the attributes contains no elements.
     ///
     /// # Examples
@@ -282,6 +310,36 @@ impl AttributesMap {
     pub fn remove(&mut self, key: &String) -> Option<Value> {
         self.0.remove(key)
     }
+
+    /// Get the typed [Format] of an attribute
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use quill_delta_rs::{attributes, AttributesMap, Format};
+    ///
+    /// let map = attributes!("header" => 1);
+    /// assert_eq!(Some(Format::Header(1)), map.get_format("header"));
+    /// assert_eq!(None, map.get_format("bold"));
+    /// ```
+    pub fn get_format(&self, key: &str) -> Option<Format> {
+        self.0.get(key).map(|value| Format::from_entry(key, value))
+    }
+
+    /// Inserts a typed [Format] in the attributes map, returning the previous value
+    /// of its key if any.
+    pub fn insert_format(&mut self, format: Format) -> Option<Value> {
+        let (key, value) = format.into_entry();
+        self.0.insert(key, value)
+    }
+
+    /// All attributes as typed [Format]s, in arbitrary order
+    pub fn formats(&self) -> Vec<Format> {
+        self.0
+            .iter()
+            .map(|(key, value)| Format::from_entry(key, value))
+            .collect()
+    }
 }
 
 impl Index<&String> for AttributesMap {
@@ -334,6 +392,12 @@ impl FromIterator<(String, Value)> for AttributesMap {
     }
 }
 
+impl FromIterator<Format> for AttributesMap {
+    fn from_iter<T: IntoIterator<Item = Format>>(iter: T) -> AttributesMap {
+        iter.into_iter().map(Format::into_entry).collect()
+    }
+}
+
 impl<'a, const N: usize> From<[(&'a str, Value); N]> for AttributesMap {
     /// # Examples
     ///
@@ -478,6 +542,7 @@ mod tests {
             composed,
             Some(AttributesMap::from([
                 ("keyA", Value::from("ab")),
+                ("keyANull", Value::Null),
                 ("keyB", Value::from("b")),
                 ("keyBNull", Value::Null)
             ]))
@@ -652,6 +717,31 @@ mod tests {
         )
     }
 
+    #[test]
+    fn intersect() {
+        let a = AttributesMap::from([
+            ("bold", Value::Bool(true)),
+            ("color", Value::from("red")),
+            ("font", Value::from("serif")),
+        ]);
+        let b = AttributesMap::from([
+            ("bold", Value::Bool(true)),
+            ("color", Value::from("blue")),
+            ("italic", Value::Bool(true)),
+        ]);
+        assert_eq!(
+            Some(AttributesMap::from([("bold", Value::Bool(true))])),
+            AttributesMap::intersect(a, b)
+        );
+    }
+
+    #[test]
+    fn intersect_disjoint() {
+        let a = AttributesMap::from([("bold", Value::Bool(true))]);
+        let b = AttributesMap::from([("italic", Value::Bool(true))]);
+        assert!(AttributesMap::intersect(a, b).is_none());
+    }
+
     #[test]
     fn attributes_macro() {
         assert_eq!(
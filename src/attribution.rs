@@ -0,0 +1,342 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{delta::Delta, iter::Iterator, op::Op, range::Range};
+
+/// Author and revision of a range of a document
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Authorship {
+    pub range: Range,
+    pub author: String,
+    pub revision: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+struct Span {
+    length: usize,
+    author: String,
+    revision: usize,
+}
+
+/// Author and revision that inserted each character of a document
+///
+/// Stored as runs of consecutive characters inserted by the same author at the same
+/// revision, and updated by every change made to the document. Format changes don't
+/// change authorship.
+///
+/// # Example
+///
+/// ```
+/// use quill_delta_rs::{Attribution, Delta, Range};
+///
+/// let document = Delta::new().insert("Hello\n", None);
+/// let mut attribution = Attribution::from_document(&document, "alice", 0);
+///
+/// let change = Delta::new().retain(5, None).insert(" World", None);
+/// attribution.apply(&change, "bob", 1);
+///
+/// assert_eq!(Some(("bob", 1)), attribution.author_at(6));
+/// let authors: Vec<_> = attribution
+///     .authorship(3, 5)
+///     .into_iter()
+///     .map(|authorship| (authorship.range, authorship.author))
+///     .collect();
+/// assert_eq!(
+///     vec![
+///         (Range::new(3, 2), "alice".to_string()),
+///         (Range::new(5, 3), "bob".to_string())
+///     ],
+///     authors
+/// );
+/// ```
+#[derive(Default, Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct Attribution {
+    spans: Vec<Span>,
+}
+
+impl Attribution {
+    /// Create an empty [Attribution], of an empty document.
+    pub fn new() -> Self {
+        Attribution { spans: Vec::new() }
+    }
+
+    /// Attribute a whole document to a single author
+    pub fn from_document(document: &Delta, author: &str, revision: usize) -> Self {
+        let mut attribution = Attribution::new();
+        attribution.push(document.len(), author, revision);
+        attribution
+    }
+
+    /// Length of the attributed document
+    pub fn len(&self) -> usize {
+        self.spans.iter().map(|span| span.length).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+
+    fn push(&mut self, length: usize, author: &str, revision: usize) {
+        if length == 0 {
+            return;
+        }
+        if let Some(last) = self.spans.last_mut()
+            && last.author == author
+            && last.revision == revision
+        {
+            last.length += length;
+            return;
+        }
+        self.spans.push(Span {
+            length,
+            author: author.to_string(),
+            revision,
+        });
+    }
+
+    /// Update the attribution with a change made by `author` at `revision`
+    pub fn apply(&mut self, change: &Delta, author: &str, revision: usize) {
+        let spans = std::mem::take(&mut self.spans);
+        let mut spans = spans.into_iter();
+        let mut current: Option<Span> = None;
+        // Take up to `length` characters from the old spans
+        let mut take = |length: usize| -> Option<Span> {
+            let mut span = current.take().or_else(|| spans.next())?;
+            if span.length > length {
+                current = Some(Span {
+                    length: span.length - length,
+                    ..span.clone()
+                });
+                span.length = length;
+            }
+            Some(span)
+        };
+
+        for op in change.ops() {
+            let mut length = op.len();
+            if op.is_insert() {
+                self.push(length, author, revision);
+                continue;
+            }
+            while length > 0 {
+                let Some(span) = take(length) else {
+                    break;
+                };
+                length -= span.length;
+                if op.is_retain() {
+                    self.push(span.length, &span.author, span.revision);
+                }
+            }
+        }
+        while let Some(span) = take(usize::MAX) {
+            self.push(span.length, &span.author, span.revision);
+        }
+    }
+
+    /// Author and revision of the character at `index`
+    pub fn author_at(&self, index: usize) -> Option<(&str, usize)> {
+        let mut offset = 0;
+        self.spans.iter().find_map(|span| {
+            offset += span.length;
+            (index < offset).then_some((span.author.as_str(), span.revision))
+        })
+    }
+
+    /// Authorship of the runs of characters of a range
+    pub fn authorship(&self, index: usize, length: usize) -> Vec<Authorship> {
+        let end = index + length;
+        let mut offset = 0;
+        let mut authorship = Vec::new();
+        for span in &self.spans {
+            let start = offset.max(index);
+            let stop = (offset + span.length).min(end);
+            if start < stop {
+                authorship.push(Authorship {
+                    range: Range::new(start, stop - start),
+                    author: span.author.clone(),
+                    revision: span.revision,
+                });
+            }
+            offset += span.length;
+            if offset >= end {
+                break;
+            }
+        }
+        authorship
+    }
+
+    /// Export the attributed `document`, with the author of its text and embeds as the
+    /// attribute `key`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use quill_delta_rs::{Attribution, Delta, {attributes, AttributesMap}};
+    ///
+    /// let document = Delta::new().insert("Hi\n", None);
+    /// let mut attribution = Attribution::from_document(&document, "alice", 0);
+    /// let change = Delta::new().insert("Oh ", None);
+    /// attribution.apply(&change, "bob", 1);
+    ///
+    /// assert_eq!(
+    ///     Delta::new()
+    ///         .insert("Oh ", Some(attributes!("author" => "bob")))
+    ///         .insert("Hi\n", Some(attributes!("author" => "alice"))),
+    ///     attribution.to_delta(&document.compose(&change), "author")
+    /// );
+    /// ```
+    pub fn to_delta(&self, document: &Delta, key: &str) -> Delta {
+        let mut iter = Iterator::from(document.ops().clone());
+        let mut delta = Delta::new();
+        for span in &self.spans {
+            let mut length = span.length;
+            while length > 0 && iter.has_next() {
+                let op = iter.next_len(length);
+                length -= op.len();
+                if op.is_insert() {
+                    let mut attributes = op.attributes().cloned().unwrap_or_default();
+                    attributes.insert(key, span.author.as_str());
+                    delta.push(Op::insert_any(op.value(), Some(attributes)));
+                } else {
+                    delta.push(op);
+                }
+            }
+        }
+        for op in iter.rest() {
+            delta.push(op);
+        }
+        delta
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{AttributesMap, Delta, Op, Range};
+
+    use super::{Attribution, Authorship};
+
+    fn attribution() -> Attribution {
+        let mut attribution =
+            Attribution::from_document(&Delta::new().insert("The fox\n", None), "alice", 0);
+        attribution.apply(&Delta::new().retain(4, None).insert("red ", None), "bob", 1);
+        attribution
+    }
+
+    fn authors(attribution: &Attribution) -> Vec<(usize, String, usize)> {
+        attribution
+            .authorship(0, attribution.len())
+            .into_iter()
+            .map(|a| (a.range.length, a.author, a.revision))
+            .collect()
+    }
+
+    #[test]
+    fn insert() {
+        assert_eq!(
+            vec![
+                (4, "alice".to_string(), 0),
+                (4, "bob".to_string(), 1),
+                (4, "alice".to_string(), 0)
+            ],
+            authors(&attribution())
+        );
+    }
+
+    #[test]
+    fn delete() {
+        let mut attribution = attribution();
+        attribution.apply(&Delta::new().retain(2, None).delete(8), "carol", 2);
+        assert_eq!(vec![(4, "alice".to_string(), 0)], authors(&attribution));
+    }
+
+    #[test]
+    fn format_keeps_author() {
+        let mut formatted = attribution();
+        formatted.apply(
+            &Delta::new().retain(10, Some(attributes!("bold" => true))),
+            "carol",
+            2,
+        );
+        assert_eq!(attribution(), formatted);
+    }
+
+    #[test]
+    fn merges_runs() {
+        let mut attribution = attribution();
+        attribution.apply(&Delta::new().retain(4, None).delete(4), "carol", 2);
+        assert_eq!(vec![(8, "alice".to_string(), 0)], authors(&attribution));
+    }
+
+    #[test]
+    fn author_at() {
+        let attribution = attribution();
+        assert_eq!(Some(("alice", 0)), attribution.author_at(3));
+        assert_eq!(Some(("bob", 1)), attribution.author_at(4));
+        assert_eq!(Some(("alice", 0)), attribution.author_at(11));
+        assert_eq!(None, attribution.author_at(12));
+    }
+
+    #[test]
+    fn authorship() {
+        assert_eq!(
+            vec![Authorship {
+                range: Range::new(5, 2),
+                author: "bob".to_string(),
+                revision: 1
+            }],
+            attribution().authorship(5, 2)
+        );
+        assert!(attribution().authorship(12, 2).is_empty());
+    }
+
+    #[test]
+    fn to_delta() {
+        let document = Delta::from(vec![
+            Op::insert("The ", None),
+            Op::insert("red ", Some(attributes!("bold" => true))),
+            Op::insert(json!({"image": "fox.png"}), None),
+            Op::insert("\n", None),
+        ]);
+        let mut attribution =
+            Attribution::from_document(&Delta::new().insert("The ", None), "alice", 0);
+        attribution.apply(
+            &Delta::from(vec![
+                Op::retain(4, None),
+                Op::insert("red ", None),
+                Op::insert(json!({"image": "fox.png"}), None),
+                Op::insert("\n", None),
+            ]),
+            "bob",
+            1,
+        );
+        assert_eq!(
+            Delta::from(vec![
+                Op::insert("The ", Some(attributes!("author" => "alice"))),
+                Op::insert("red ", Some(attributes!("bold" => true, "author" => "bob"))),
+                Op::insert_any(
+                    json!({"image": "fox.png"}),
+                    Some(attributes!("author" => "bob"))
+                ),
+                Op::insert("\n", Some(attributes!("author" => "bob"))),
+            ]),
+            attribution.to_delta(&document, "author")
+        );
+    }
+
+    #[test]
+    fn serde() {
+        let attribution = attribution();
+        let value = serde_json::to_value(&attribution).unwrap();
+        assert_eq!(
+            json!([
+                {"length": 4, "author": "alice", "revision": 0},
+                {"length": 4, "author": "bob", "revision": 1},
+                {"length": 4, "author": "alice", "revision": 0},
+            ]),
+            value
+        );
+        assert_eq!(attribution, serde_json::from_value(value).unwrap());
+    }
+}
@@ -0,0 +1,611 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    io::{self, BufReader, Read, Write},
+    string::FromUtf8Error,
+};
+
+use serde_json::{Map, Number, Value};
+
+use crate::{
+    attributes::AttributesMap,
+    delta::Delta,
+    op::{Op, OpType},
+};
+
+/// Version of the binary format, first byte of an encoded [Delta]
+const VERSION: u8 = 1;
+
+const RETAIN: u8 = 0;
+const DELETE: u8 = 1;
+const TEXT: u8 = 2;
+const EMBED: u8 = 3;
+/// Flag of the op header set when attributes follow
+const ATTRIBUTES: u8 = 4;
+
+const NULL: u8 = 0;
+const FALSE: u8 = 1;
+const TRUE: u8 = 2;
+const UNSIGNED: u8 = 3;
+const NEGATIVE: u8 = 4;
+const FLOAT: u8 = 5;
+const STRING: u8 = 6;
+const ARRAY: u8 = 7;
+const OBJECT: u8 = 8;
+
+/// Maximum nesting of arrays and objects, as serde_json's recursion limit
+const MAX_DEPTH: usize = 128;
+
+/// An error decoding a binary [Delta]
+#[derive(Debug)]
+pub enum DecodeError {
+    /// Reading failed, or the input ended in the middle of an op
+    Io(io::Error),
+    /// The input is not in a supported version of the format
+    UnsupportedVersion(u8),
+    /// An op or value starts with an unknown tag
+    UnknownTag(u8),
+    /// A string refers to a string not defined yet
+    UnknownString(usize),
+    /// A string is not valid UTF-8
+    InvalidUtf8(FromUtf8Error),
+    /// The decoded op or value is not valid
+    Invalid(&'static str),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Io(error) => write!(f, "failed to read binary delta: {error}"),
+            DecodeError::UnsupportedVersion(version) => {
+                write!(f, "unsupported binary delta version {version}")
+            }
+            DecodeError::UnknownTag(tag) => write!(f, "unknown tag {tag}"),
+            DecodeError::UnknownString(index) => write!(f, "unknown string reference {index}"),
+            DecodeError::InvalidUtf8(error) => write!(f, "invalid string: {error}"),
+            DecodeError::Invalid(reason) => write!(f, "invalid binary delta: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DecodeError::Io(error) => Some(error),
+            DecodeError::InvalidUtf8(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for DecodeError {
+    fn from(error: io::Error) -> Self {
+        DecodeError::Io(error)
+    }
+}
+
+impl From<FromUtf8Error> for DecodeError {
+    fn from(error: FromUtf8Error) -> Self {
+        DecodeError::InvalidUtf8(error)
+    }
+}
+
+/// Strings written so far, referred to by their index plus one, 0 introducing a new string
+#[derive(Default)]
+struct Interner(HashMap<String, usize>);
+
+impl Interner {
+    fn write<W: Write>(&mut self, writer: &mut W, string: &str) -> io::Result<()> {
+        if let Some(index) = self.0.get(string) {
+            return write_varint(writer, *index as u64 + 1);
+        }
+        write_varint(writer, 0)?;
+        write_bytes(writer, string.as_bytes())?;
+        self.0.insert(string.to_string(), self.0.len());
+        Ok(())
+    }
+}
+
+fn write_varint<W: Write>(writer: &mut W, mut value: u64) -> io::Result<()> {
+    let mut buffer = [0; 10];
+    let mut length = 0;
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            buffer[length] = byte;
+            length += 1;
+            break;
+        }
+        buffer[length] = byte | 0x80;
+        length += 1;
+    }
+    writer.write_all(&buffer[..length])
+}
+
+fn write_bytes<W: Write>(writer: &mut W, bytes: &[u8]) -> io::Result<()> {
+    write_varint(writer, bytes.len() as u64)?;
+    writer.write_all(bytes)
+}
+
+/// Writer of [Op]s in the compact binary format
+///
+/// Every op starts with a byte holding its kind and whether it has attributes. Retains and
+/// deletes follow with their length as a varint, inserted text with its length and UTF-8
+/// bytes, embeds and attribute values as tagged values. Attribute and object keys are
+/// written once and then referred to by index, as are the string values of attributes and
+/// embeds. Values nested more than 128 levels deep can't be written.
+///
+/// # Example
+///
+/// ```
+/// use quill_delta_rs::{Decoder, Delta, Encoder, Op};
+///
+/// let mut encoder = Encoder::new(Vec::new()).unwrap();
+/// encoder.encode(&Op::insert("Hello", None)).unwrap();
+/// encoder.encode(&Op::retain(3, None)).unwrap();
+/// let bytes = encoder.into_inner();
+///
+/// let ops: Vec<Op> = Decoder::new(bytes.as_slice())
+///     .unwrap()
+///     .collect::<Result<_, _>>()
+///     .unwrap();
+/// assert_eq!(vec![Op::insert("Hello", None), Op::retain(3, None)], ops);
+/// ```
+pub struct Encoder<W: Write> {
+    writer: W,
+    keys: Interner,
+    strings: Interner,
+}
+
+impl<W: Write> Encoder<W> {
+    /// Create an [Encoder], writing the format version to `writer`
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        writer.write_all(&[VERSION])?;
+        Ok(Encoder {
+            writer,
+            keys: Interner::default(),
+            strings: Interner::default(),
+        })
+    }
+
+    /// Write an op
+    pub fn encode(&mut self, op: &Op) -> io::Result<()> {
+        let (kind, length) = match op.kind() {
+            OpType::Retain(length) => (RETAIN, *length),
+            OpType::Delete(length) => (DELETE, *length),
+            OpType::Insert(Value::String(text)) => (TEXT, text.len()),
+            OpType::Insert(_) => (EMBED, 1),
+        };
+        let attributes = op.attributes();
+        let flag = if attributes.is_some() { ATTRIBUTES } else { 0 };
+        self.writer.write_all(&[kind | flag])?;
+        match op.kind() {
+            OpType::Insert(Value::String(text)) => write_bytes(&mut self.writer, text.as_bytes())?,
+            OpType::Insert(value) => self.encode_value(value, 0)?,
+            _ => write_varint(&mut self.writer, length as u64)?,
+        }
+        if let Some(attributes) = attributes {
+            // Sorted for the output not to depend on the order of the map
+            let mut attributes: Vec<_> = attributes.into_iter().collect();
+            attributes.sort_by(|(a, _), (b, _)| a.cmp(b));
+            write_varint(&mut self.writer, attributes.len() as u64)?;
+            for (key, value) in attributes {
+                self.keys.write(&mut self.writer, &key)?;
+                self.encode_value(&value, 0)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn encode_value(&mut self, value: &Value, depth: usize) -> io::Result<()> {
+        if depth > MAX_DEPTH {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "value nested too deeply",
+            ));
+        }
+        match value {
+            Value::Null => self.writer.write_all(&[NULL]),
+            Value::Bool(false) => self.writer.write_all(&[FALSE]),
+            Value::Bool(true) => self.writer.write_all(&[TRUE]),
+            Value::Number(number) => {
+                if let Some(number) = number.as_u64() {
+                    self.writer.write_all(&[UNSIGNED])?;
+                    write_varint(&mut self.writer, number)
+                } else if let Some(number) = number.as_i64() {
+                    self.writer.write_all(&[NEGATIVE])?;
+                    write_varint(&mut self.writer, !number as u64)
+                } else {
+                    self.writer.write_all(&[FLOAT])?;
+                    let number = number.as_f64().unwrap_or_default();
+                    self.writer.write_all(&number.to_le_bytes())
+                }
+            }
+            Value::String(string) => {
+                self.writer.write_all(&[STRING])?;
+                self.strings.write(&mut self.writer, string)
+            }
+            Value::Array(values) => {
+                self.writer.write_all(&[ARRAY])?;
+                write_varint(&mut self.writer, values.len() as u64)?;
+                for value in values {
+                    self.encode_value(value, depth + 1)?;
+                }
+                Ok(())
+            }
+            Value::Object(map) => {
+                self.writer.write_all(&[OBJECT])?;
+                write_varint(&mut self.writer, map.len() as u64)?;
+                for (key, value) in map {
+                    self.keys.write(&mut self.writer, key)?;
+                    self.encode_value(value, depth + 1)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// The underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Streaming reader of [Op]s in the compact binary format written by [Encoder]
+///
+/// Ops are decoded one at a time as the [Decoder] is iterated, so that a large [Delta] can
+/// be applied without being held in memory. Iteration stops after the first error. The input
+/// is buffered internally, so a raw socket or file can be passed as is.
+pub struct Decoder<R: Read> {
+    reader: BufReader<R>,
+    keys: Vec<String>,
+    strings: Vec<String>,
+    done: bool,
+}
+
+impl<R: Read> Decoder<R> {
+    /// Create a [Decoder], reading and checking the format version from `reader`
+    pub fn new(reader: R) -> Result<Self, DecodeError> {
+        let mut reader = BufReader::new(reader);
+        let mut version = [0];
+        reader.read_exact(&mut version)?;
+        if version[0] != VERSION {
+            return Err(DecodeError::UnsupportedVersion(version[0]));
+        }
+        Ok(Decoder {
+            reader,
+            keys: Vec::new(),
+            strings: Vec::new(),
+            done: false,
+        })
+    }
+
+    /// Read the next byte, [None] at the end of the input
+    fn read_byte(&mut self) -> io::Result<Option<u8>> {
+        let mut byte = [0];
+        loop {
+            match self.reader.read(&mut byte) {
+                Ok(0) => return Ok(None),
+                Ok(_) => return Ok(Some(byte[0])),
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
+    fn read_tag(&mut self) -> Result<u8, DecodeError> {
+        self.read_byte()?
+            .ok_or(io::Error::from(io::ErrorKind::UnexpectedEof).into())
+    }
+
+    fn read_varint(&mut self) -> Result<u64, DecodeError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.read_tag()?;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(DecodeError::Invalid("varint overflow"))
+    }
+
+    fn read_length(&mut self) -> Result<usize, DecodeError> {
+        usize::try_from(self.read_varint()?).map_err(|_| DecodeError::Invalid("length overflow"))
+    }
+
+    fn read_string(&mut self) -> Result<String, DecodeError> {
+        let length = self.read_varint()?;
+        let mut bytes = Vec::new();
+        (&mut self.reader).take(length).read_to_end(&mut bytes)?;
+        if bytes.len() as u64 != length {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        Ok(String::from_utf8(bytes)?)
+    }
+
+    fn read_interned(&mut self, key: bool) -> Result<String, DecodeError> {
+        let index = self.read_length()?;
+        if index == 0 {
+            let string = self.read_string()?;
+            let table = if key {
+                &mut self.keys
+            } else {
+                &mut self.strings
+            };
+            table.push(string.clone());
+            return Ok(string);
+        }
+        let table = if key { &self.keys } else { &self.strings };
+        table
+            .get(index - 1)
+            .cloned()
+            .ok_or(DecodeError::UnknownString(index))
+    }
+
+    fn decode_value(&mut self, depth: usize) -> Result<Value, DecodeError> {
+        if depth > MAX_DEPTH {
+            return Err(DecodeError::Invalid("value nested too deeply"));
+        }
+        Ok(match self.read_tag()? {
+            NULL => Value::Null,
+            FALSE => Value::Bool(false),
+            TRUE => Value::Bool(true),
+            UNSIGNED => Value::from(self.read_varint()?),
+            NEGATIVE => Value::from(!(self.read_varint()? as i64)),
+            FLOAT => {
+                let mut bytes = [0; 8];
+                self.reader.read_exact(&mut bytes)?;
+                Number::from_f64(f64::from_le_bytes(bytes))
+                    .map(Value::Number)
+                    .ok_or(DecodeError::Invalid("non finite number"))?
+            }
+            STRING => Value::String(self.read_interned(false)?),
+            ARRAY => {
+                let length = self.read_length()?;
+                let mut values = Vec::new();
+                for _ in 0..length {
+                    values.push(self.decode_value(depth + 1)?);
+                }
+                Value::Array(values)
+            }
+            OBJECT => {
+                let length = self.read_length()?;
+                let mut map = Map::new();
+                for _ in 0..length {
+                    let key = self.read_interned(true)?;
+                    map.insert(key, self.decode_value(depth + 1)?);
+                }
+                Value::Object(map)
+            }
+            tag => return Err(DecodeError::UnknownTag(tag)),
+        })
+    }
+
+    fn decode(&mut self, header: u8) -> Result<Op, DecodeError> {
+        let kind = header & !ATTRIBUTES;
+        let value = match kind {
+            RETAIN | DELETE => None,
+            TEXT => Some(Value::String(self.read_string()?)),
+            EMBED => Some(self.decode_value(0)?),
+            _ => return Err(DecodeError::UnknownTag(header)),
+        };
+        let length = match value {
+            None => self.read_length()?,
+            Some(_) => 1,
+        };
+        if length == 0 {
+            return Err(DecodeError::Invalid("zero length op"));
+        }
+        let attributes = if header & ATTRIBUTES != 0 {
+            let count = self.read_length()?;
+            let mut attributes = AttributesMap::new();
+            for _ in 0..count {
+                let key = self.read_interned(true)?;
+                attributes.insert(key, self.decode_value(0)?);
+            }
+            Some(attributes)
+        } else {
+            None
+        };
+        match (kind, value) {
+            (RETAIN, _) => Ok(Op::retain(length, attributes)),
+            (DELETE, _) if attributes.is_some() => {
+                Err(DecodeError::Invalid("attributes on a delete"))
+            }
+            (DELETE, _) => Ok(Op::delete(length)),
+            (_, Some(value)) => Ok(Op::insert_any(value, attributes)),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<R: Read> std::iter::Iterator for Decoder<R> {
+    type Item = Result<Op, DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let op = match self.read_byte() {
+            Ok(None) => {
+                self.done = true;
+                return None;
+            }
+            Ok(Some(header)) => self.decode(header),
+            Err(error) => Err(error.into()),
+        };
+        self.done = op.is_err();
+        Some(op)
+    }
+}
+
+impl Delta {
+    /// Encode this [Delta] in the compact binary format, see [Encoder]
+    ///
+    /// # Panics
+    ///
+    /// If an embed or attribute value is nested more than 128 levels deep
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use quill_delta_rs::{Delta, {attributes, AttributesMap}};
+    ///
+    /// let delta = Delta::new()
+    ///     .insert("Hello", Some(attributes!("bold" => true)))
+    ///     .insert(" World", Some(attributes!("bold" => true, "italic" => true)));
+    /// let bytes = delta.to_binary();
+    /// assert!(bytes.len() < serde_json::to_vec(&delta).unwrap().len() / 2);
+    /// assert_eq!(delta, Delta::from_binary(&bytes).unwrap());
+    /// ```
+    pub fn to_binary(&self) -> Vec<u8> {
+        let mut encoder = Encoder::new(Vec::new()).unwrap();
+        for op in self.ops() {
+            encoder.encode(op).expect("value nested too deeply");
+        }
+        encoder.into_inner()
+    }
+
+    /// Decode a [Delta] from the compact binary format, see [Decoder]
+    pub fn from_binary(bytes: &[u8]) -> Result<Delta, DecodeError> {
+        let ops = Decoder::new(bytes)?.collect::<Result<Vec<_>, _>>()?;
+        Ok(Delta::from(ops))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Read};
+
+    use serde_json::json;
+
+    use crate::{AttributesMap, Delta, Op};
+
+    use super::{DecodeError, Decoder};
+
+    fn delta() -> Delta {
+        Delta::from(vec![
+            Op::insert("Hello", Some(attributes!("bold" => true, "color" => "red"))),
+            Op::insert(" ", None),
+            Op::insert("Wörld", Some(attributes!("color" => "red", "size" => -2))),
+            Op::insert(
+                json!({"image": "fox.png", "size": [1.5, 300, null, {"alt": "red"}]}),
+                None,
+            ),
+            Op::retain(
+                usize::MAX,
+                Some(attributes!("bold" => serde_json::Value::Null)),
+            ),
+            Op::delete(42),
+        ])
+    }
+
+    #[test]
+    fn round_trip() {
+        let delta = delta();
+        let decoded = Delta::from_binary(&delta.to_binary()).unwrap();
+        assert_eq!(delta, decoded);
+        assert_eq!(
+            serde_json::to_value(&delta).unwrap(),
+            serde_json::to_value(&decoded).unwrap()
+        );
+        assert_eq!(
+            Delta::new(),
+            Delta::from_binary(&Delta::new().to_binary()).unwrap()
+        );
+    }
+
+    #[test]
+    fn attributed_embed() {
+        let delta = Delta::from(vec![
+            Op::insert_any(
+                json!({"image": "fox.png"}),
+                Some(attributes!("width" => "120", "link" => "https://example.com")),
+            ),
+            Op::insert("\n", None),
+        ]);
+        assert_eq!(delta, Delta::from_binary(&delta.to_binary()).unwrap());
+    }
+
+    #[test]
+    fn interning() {
+        let op = Op::insert("a", Some(attributes!("color" => "#ff0000")));
+        let once = Delta::from(vec![op.clone()]).to_binary().len();
+        let twice = Delta::from(vec![op.clone(), op]).to_binary().len();
+        // header, text, count, key and value references
+        assert_eq!(once + 7, twice);
+    }
+
+    #[test]
+    fn streaming() {
+        let bytes = delta().to_binary();
+        let mut decoder = Decoder::new(bytes.as_slice()).unwrap();
+        assert_eq!(delta().ops()[0], decoder.next().unwrap().unwrap());
+        assert_eq!(delta().ops().len() - 1, decoder.count());
+    }
+
+    #[test]
+    fn buffered() {
+        struct CountingReader<'a> {
+            bytes: &'a [u8],
+            reads: usize,
+        }
+
+        impl Read for CountingReader<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                self.reads += 1;
+                self.bytes.read(buf)
+            }
+        }
+
+        let bytes = delta().to_binary();
+        let mut reader = CountingReader {
+            bytes: &bytes,
+            reads: 0,
+        };
+        let decoded = Decoder::new(&mut reader)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(delta().ops(), decoded.as_slice());
+        // The whole input and the end of the input
+        assert_eq!(2, reader.reads);
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(matches!(Delta::from_binary(&[]), Err(DecodeError::Io(_))));
+        assert!(matches!(
+            Delta::from_binary(&[2]),
+            Err(DecodeError::UnsupportedVersion(2))
+        ));
+        assert!(matches!(
+            Delta::from_binary(&[1, 9]),
+            Err(DecodeError::UnknownTag(9))
+        ));
+        assert!(matches!(
+            Delta::from_binary(&[1, 0, 0]),
+            Err(DecodeError::Invalid(_))
+        ));
+        // Truncated text
+        assert!(matches!(
+            Delta::from_binary(&[1, 2, 3, b'a']),
+            Err(DecodeError::Io(_))
+        ));
+        // Reference to an undefined key
+        assert!(matches!(
+            Delta::from_binary(&[1, 4, 1, 1, 1, 0]),
+            Err(DecodeError::UnknownString(1))
+        ));
+
+        let bytes = delta().to_binary();
+        let mut decoder = Decoder::new(&bytes[..bytes.len() - 1]).unwrap();
+        assert_eq!(
+            delta().ops().len() - 1,
+            decoder.by_ref().take_while(Result::is_ok).count()
+        );
+        assert!(decoder.next().is_none());
+    }
+}
@@ -0,0 +1,256 @@
+use std::fmt;
+
+use crate::delta::Delta;
+
+/// An error of a [Client] receiving an unexpected message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClientError {
+    /// An acknowledgement was received while no change was awaiting confirmation
+    UnexpectedAck,
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::UnexpectedAck => write!(f, "no change is awaiting acknowledgement"),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+/// State of a [Client]
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub enum ClientState {
+    /// Every local change is acknowledged by the server
+    #[default]
+    Synchronized,
+    /// A change was sent and is awaiting acknowledgement
+    AwaitingConfirm(Delta),
+    /// A change was sent and is awaiting acknowledgement, while local changes made since
+    /// are buffered
+    AwaitingWithBuffer(Delta, Delta),
+}
+
+/// Client side of operational transformation
+///
+/// Tracks the last server revision the client has seen and at most one change in flight.
+/// Local changes are sent one at a time: those made while awaiting acknowledgement are
+/// composed into a buffer, sent once the server acknowledges the outstanding change.
+/// Changes received from the server are transformed against the outstanding and buffered
+/// changes before being applied locally, consistently with [Server](crate::Server), which
+/// gives priority to changes it applied first.
+///
+/// # Example
+///
+/// ```
+/// use quill_delta_rs::{Client, Delta, Server};
+///
+/// let mut server = Server::new(Delta::new().insert("Hello\n", None));
+/// let mut client = Client::new(0);
+///
+/// let local = Delta::new().retain(5, None).insert("!", None);
+/// let (revision, change) = client.apply_client(local).unwrap();
+///
+/// // Another client's change is applied first
+/// let remote = server.receive(0, Delta::new().insert("Oh ", None)).unwrap();
+/// let mine = server.receive(revision, change).unwrap();
+///
+/// assert_eq!(Delta::new().insert("Oh ", None), client.apply_server(remote));
+/// assert_eq!(None, client.server_ack().unwrap());
+/// assert_eq!(2, client.revision());
+/// assert_eq!(Delta::new().retain(8, None).insert("!", None), mine);
+/// ```
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Client {
+    revision: usize,
+    state: ClientState,
+}
+
+impl Client {
+    /// Create a synchronized [Client] of a document at `revision`
+    pub fn new(revision: usize) -> Self {
+        Client {
+            revision,
+            state: ClientState::Synchronized,
+        }
+    }
+
+    /// The last server revision the client has seen
+    pub fn revision(&self) -> usize {
+        self.revision
+    }
+
+    pub fn state(&self) -> &ClientState {
+        &self.state
+    }
+
+    /// Handle a change made locally
+    ///
+    /// Returns the revision and change to send to the server, if no change is in flight.
+    pub fn apply_client(&mut self, change: Delta) -> Option<(usize, Delta)> {
+        match std::mem::take(&mut self.state) {
+            ClientState::Synchronized => {
+                self.state = ClientState::AwaitingConfirm(change.clone());
+                Some((self.revision, change))
+            }
+            ClientState::AwaitingConfirm(outstanding) => {
+                self.state = ClientState::AwaitingWithBuffer(outstanding, change);
+                None
+            }
+            ClientState::AwaitingWithBuffer(outstanding, buffer) => {
+                self.state = ClientState::AwaitingWithBuffer(outstanding, buffer.compose(&change));
+                None
+            }
+        }
+    }
+
+    /// Handle a change of another client broadcast by the server
+    ///
+    /// Returns the change to apply to the local document.
+    pub fn apply_server(&mut self, change: Delta) -> Delta {
+        self.revision += 1;
+        match std::mem::take(&mut self.state) {
+            ClientState::Synchronized => change,
+            ClientState::AwaitingConfirm(outstanding) => {
+                self.state = ClientState::AwaitingConfirm(change.transform(&outstanding, true));
+                outstanding.transform(&change, false)
+            }
+            ClientState::AwaitingWithBuffer(outstanding, buffer) => {
+                let outstanding_rebased = change.transform(&outstanding, true);
+                let change = outstanding.transform(&change, false);
+                let buffer_rebased = change.transform(&buffer, true);
+                let change = buffer.transform(&change, false);
+                self.state = ClientState::AwaitingWithBuffer(outstanding_rebased, buffer_rebased);
+                change
+            }
+        }
+    }
+
+    /// Handle the acknowledgement of the change in flight
+    ///
+    /// Returns the revision and buffered change to send next, if any.
+    pub fn server_ack(&mut self) -> Result<Option<(usize, Delta)>, ClientError> {
+        match std::mem::take(&mut self.state) {
+            ClientState::Synchronized => Err(ClientError::UnexpectedAck),
+            ClientState::AwaitingConfirm(_) => {
+                self.revision += 1;
+                Ok(None)
+            }
+            ClientState::AwaitingWithBuffer(_, buffer) => {
+                self.revision += 1;
+                self.state = ClientState::AwaitingConfirm(buffer.clone());
+                Ok(Some((self.revision, buffer)))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Delta, Server};
+
+    use super::{Client, ClientError, ClientState};
+
+    #[test]
+    fn synchronized() {
+        let mut client = Client::new(3);
+        let remote = Delta::new().insert("A", None);
+        assert_eq!(remote, client.apply_server(remote.clone()));
+        assert_eq!(4, client.revision());
+        assert_eq!(&ClientState::Synchronized, client.state());
+        assert_eq!(Err(ClientError::UnexpectedAck), client.server_ack());
+    }
+
+    #[test]
+    fn awaiting_confirm() {
+        let mut client = Client::new(0);
+        let local = Delta::new().insert("A", None);
+        assert_eq!(Some((0, local.clone())), client.apply_client(local.clone()));
+        assert_eq!(&ClientState::AwaitingConfirm(local), client.state());
+        assert_eq!(Ok(None), client.server_ack());
+        assert_eq!(1, client.revision());
+        assert_eq!(&ClientState::Synchronized, client.state());
+    }
+
+    #[test]
+    fn awaiting_with_buffer() {
+        let mut client = Client::new(0);
+        client.apply_client(Delta::new().insert("A", None));
+        assert_eq!(None, client.apply_client(Delta::new().insert("B", None)));
+        assert_eq!(
+            None,
+            client.apply_client(Delta::new().retain(1, None).insert("C", None))
+        );
+        assert_eq!(
+            &ClientState::AwaitingWithBuffer(
+                Delta::new().insert("A", None),
+                Delta::new().insert("BC", None)
+            ),
+            client.state()
+        );
+        assert_eq!(
+            Ok(Some((1, Delta::new().insert("BC", None)))),
+            client.server_ack()
+        );
+        assert_eq!(
+            &ClientState::AwaitingConfirm(Delta::new().insert("BC", None)),
+            client.state()
+        );
+    }
+
+    #[test]
+    fn transform_server_change() {
+        let mut client = Client::new(0);
+        client.apply_client(Delta::new().retain(2, None).insert("A", None));
+        client.apply_client(Delta::new().retain(4, None).insert("B", None));
+        let change = client.apply_server(Delta::new().insert("xy", None).delete(1));
+        assert_eq!(Delta::new().insert("xy", None).delete(1), change);
+        assert_eq!(
+            &ClientState::AwaitingWithBuffer(
+                Delta::new().retain(3, None).insert("A", None),
+                Delta::new().retain(5, None).insert("B", None)
+            ),
+            client.state()
+        );
+    }
+
+    #[test]
+    fn converges_with_server() {
+        let doc = Delta::new().insert("abc\n", None);
+        let mut server = Server::new(doc.clone());
+        let mut alice = (Client::new(0), doc.clone());
+        let mut bob = (Client::new(0), doc.clone());
+
+        let edit = |(client, doc): &mut (Client, Delta), change: Delta| {
+            *doc = doc.compose(&change);
+            client.apply_client(change)
+        };
+        let a1 = edit(&mut alice, Delta::new().retain(1, None).insert("1", None)).unwrap();
+        assert_eq!(None, edit(&mut alice, Delta::new().delete(1)));
+        let b1 = edit(&mut bob, Delta::new().retain(1, None).insert("2", None)).unwrap();
+        edit(&mut bob, Delta::new().retain(4, None).insert("!", None));
+
+        // The server receives bob's change first, then alice's
+        let broadcast_b1 = server.receive(b1.0, b1.1).unwrap();
+        let broadcast_a1 = server.receive(a1.0, a1.1).unwrap();
+
+        alice.1 = alice.1.compose(&alice.0.apply_server(broadcast_b1));
+        let a2 = alice.0.server_ack().unwrap().unwrap();
+        let b2 = bob.0.server_ack().unwrap().unwrap();
+        bob.1 = bob.1.compose(&bob.0.apply_server(broadcast_a1));
+
+        let broadcast_a2 = server.receive(a2.0, a2.1).unwrap();
+        let broadcast_b2 = server.receive(b2.0, b2.1).unwrap();
+        bob.1 = bob.1.compose(&bob.0.apply_server(broadcast_a2));
+        assert_eq!(Ok(None), bob.0.server_ack());
+        assert_eq!(Ok(None), alice.0.server_ack());
+        alice.1 = alice.1.compose(&alice.0.apply_server(broadcast_b2));
+
+        assert_eq!(server.document(), &alice.1);
+        assert_eq!(server.document(), &bob.1);
+        assert_eq!(4, alice.0.revision());
+        assert_eq!(4, bob.0.revision());
+        assert_eq!(&Delta::new().insert("21bc!\n", None), server.document());
+    }
+}
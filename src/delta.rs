@@ -1,12 +1,13 @@
 use std::{cmp::min, fmt::Display};
 
-use serde::{Deserialize, Serialize};
+use serde::Serialize;
 use serde_json::Value;
 
 use crate::{
     attributes::AttributesMap,
     iter::Iterator,
     op::{Op, OpType},
+    schema::{Schema, Scope},
 };
 
 /// Implementation of Quill editor Delta format
@@ -16,9 +17,12 @@ use crate::{
 /// > Deltas are a simple, yet expressive format that can be used to describe Quill's contents and changes.
 /// > The format is a strict subset of JSON, is human readable, and easily parsible by machines.
 /// > Deltas can describe any Quill document, includes all text and formatting information, without the ambiguity and complexity of HTML.
-#[derive(Default, Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+///
+/// A [Delta] serializes as `{"delta": [...]}`, see [JsonFormat](crate::JsonFormat) for the
+/// other forms, all of which it deserializes from.
+#[derive(Default, Debug, Serialize, Clone, PartialEq, Eq)]
 pub struct Delta {
-    #[serde(rename = "delta", alias = "ops")]
+    #[serde(rename = "delta")]
     ops: Vec<Op>,
 }
 
@@ -233,7 +237,7 @@ impl Delta {
         let mut plain_text = String::new();
         for op in &self.ops {
             if op.is_text_insert() {
-                plain_text.push_str(&op.value_as_string());
+                plain_text.push_str(op.value_as_string());
             } else {
                 plain_text.push('\n');
             }
@@ -311,6 +315,80 @@ impl Delta {
         delta
     }
 
+    /// Calls `predicate` on each line of a document [Delta], with the attributes of the
+    /// newline ending the line and the line number.
+    ///
+    /// Iteration stops when `predicate` returns `false` or when a non-insert [Op] is met.
+    /// A last line without a trailing newline is passed with empty attributes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use quill_delta_rs::{Delta, Op, {attributes, AttributesMap}};
+    ///
+    /// let delta = Delta::from(vec![
+    ///     Op::insert("Title", None),
+    ///     Op::insert("\n", Some(attributes!("header" => 1))),
+    ///     Op::insert("Body\nEnd", None),
+    /// ]);
+    /// let mut lines = Vec::new();
+    /// delta.each_line(|line, attributes, index| {
+    ///     lines.push((line.plain_text(), attributes.clone(), index));
+    ///     true
+    /// });
+    /// assert_eq!(
+    ///     vec![
+    ///         ("Title".to_string(), attributes!("header" => 1), 0),
+    ///         ("Body".to_string(), AttributesMap::new(), 1),
+    ///         ("End".to_string(), AttributesMap::new(), 2),
+    ///     ],
+    ///     lines
+    /// );
+    /// ```
+    pub fn each_line<F>(&self, mut predicate: F)
+    where
+        F: FnMut(&Delta, &AttributesMap, usize) -> bool,
+    {
+        let mut iter = Iterator::from(self.ops.clone());
+        let mut line = Delta::new();
+        let mut line_index = 0;
+        while iter.has_next() {
+            if !matches!(iter.peek_type(), OpType::Insert(_)) {
+                return;
+            }
+            let next_op = iter.peek().unwrap();
+            let newline = if next_op.is_text_insert() {
+                let start = next_op.len() - iter.peek_len();
+                next_op
+                    .value_as_string()
+                    .chars()
+                    .skip(start)
+                    .position(|c| c == '\n')
+            } else {
+                None
+            };
+            match newline {
+                None => {
+                    line.push(iter.next().unwrap());
+                }
+                Some(0) => {
+                    let attributes = iter.next_len(1).attributes().cloned().unwrap_or_default();
+                    if !predicate(&line, &attributes, line_index) {
+                        return;
+                    }
+                    line_index += 1;
+                    line = Delta::new();
+                }
+                Some(length) => {
+                    line.push(iter.next_len(length));
+                }
+            }
+        }
+        if !line.is_empty() {
+            predicate(&line, &AttributesMap::new(), line_index);
+        }
+    }
+
     /// Combines this [Delta] with another [Delta]
     ///
     /// The other [Delta] is applied over this [Delta] resulting in a combined [Delta]
@@ -393,7 +471,7 @@ impl Delta {
                     let new_op = if self_op.is_retain() {
                         Op::retain(length, attributes)
                     } else {
-                        Op::insert(self_op.value(), attributes)
+                        Op::insert_any(self_op.value(), attributes)
                     };
                     delta.push(new_op.clone());
                     // Optimization if rest of other is just retain
@@ -514,110 +592,886 @@ impl Delta {
         inverted.chop();
         inverted
     }
-}
 
-impl From<Vec<Op>> for Delta {
-    fn from(value: Vec<Op>) -> Self {
-        Delta { ops: value }
+    /// Get the change turning this document [Delta] into the `other` one
+    ///
+    /// Text is compared with a character diff, embeds being equal only to identical embeds.
+    ///
+    /// # Panics
+    ///
+    /// If either [Delta] is not a document, i.e. contains other [Op]s than inserts.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use quill_delta_rs::{Delta, {attributes, AttributesMap}};
+    ///
+    /// let a = Delta::new().insert("Hello World\n", None);
+    /// let b = Delta::new()
+    ///     .insert("Hello ", None)
+    ///     .insert("big ", Some(attributes!("bold" => true)))
+    ///     .insert("World!\n", None);
+    /// let change = a.diff(&b);
+    /// assert_eq!(
+    ///     Delta::new()
+    ///         .retain(6, None)
+    ///         .insert("big ", Some(attributes!("bold" => true)))
+    ///         .retain(5, None)
+    ///         .insert("!", None),
+    ///     change
+    /// );
+    /// assert_eq!(b, a.compose(&change));
+    /// ```
+    pub fn diff(&self, other: &Delta) -> Delta {
+        if self == other {
+            return Delta::new();
+        }
+        let text = |delta: &Delta, name: &str| {
+            delta.fold(String::new(), |mut text, op| {
+                assert!(op.is_insert(), "diff() called with non-document {name}");
+                match op.kind() {
+                    OpType::Insert(Value::String(s)) => text.push_str(s),
+                    _ => text.push('\0'),
+                }
+                text
+            })
+        };
+        let diffs = dmp::new().diff_main(&text(self, "self"), &text(other, "other"), false);
+
+        let mut iter = Iterator::from(self.ops.clone());
+        let mut other_iter = Iterator::from(other.ops.clone());
+        let mut delta = Delta::new();
+        for component in diffs {
+            let mut length = component.text.chars().count();
+            while length > 0 {
+                let op_length;
+                match component.operation {
+                    1 => {
+                        op_length = min(other_iter.peek_len(), length);
+                        delta.push(other_iter.next_len(op_length));
+                    }
+                    -1 => {
+                        op_length = min(iter.peek_len(), length);
+                        iter.next_len(op_length);
+                        delta.push(Op::delete(op_length));
+                    }
+                    _ => {
+                        op_length = min(min(iter.peek_len(), other_iter.peek_len()), length);
+                        let self_op = iter.next_len(op_length);
+                        let other_op = other_iter.next_len(op_length);
+                        if self_op.kind() == other_op.kind() {
+                            delta.push(Op::retain(
+                                op_length,
+                                AttributesMap::diff(
+                                    self_op.attributes().cloned().unwrap_or_default(),
+                                    other_op.attributes().cloned().unwrap_or_default(),
+                                ),
+                            ));
+                        } else {
+                            delta.push(other_op);
+                            delta.push(Op::delete(op_length));
+                        }
+                    }
+                }
+                length -= op_length;
+            }
+        }
+        delta.chop();
+        delta
     }
-}
 
-impl Display for Delta {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for op in &self.ops {
-            f.write_fmt(format_args!("{op}\n"))?;
+    /// Transform `other` against this [Delta]
+    ///
+    /// Both deltas are changes made concurrently to the same document. The result is `other`
+    /// rewritten to apply after this [Delta]. If `priority` is `true`, this [Delta] is
+    /// considered to happen first: its inserts go before `other`'s inserts at the same
+    /// index and its formats win over `other`'s.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use quill_delta_rs::{Delta, {attributes, AttributesMap}};
+    ///
+    /// let a = Delta::new().insert("a", None);
+    /// let b = Delta::new().insert("b", None).retain(5, Some(attributes!("bold" => true)));
+    ///
+    /// assert_eq!(
+    ///     Delta::new().retain(1, None).insert("b", None).retain(5, Some(attributes!("bold" => true))),
+    ///     a.transform(&b, true)
+    /// );
+    /// assert_eq!(
+    ///     Delta::new().insert("b", None).retain(1, None).retain(5, Some(attributes!("bold" => true))),
+    ///     a.transform(&b, false)
+    /// );
+    /// ```
+    pub fn transform(&self, other: &Delta, priority: bool) -> Delta {
+        let mut iter = Iterator::from(self.ops.clone());
+        let mut other_iter = Iterator::from(other.ops.clone());
+        let mut delta = Delta::new();
+        while iter.has_next() || other_iter.has_next() {
+            if matches!(iter.peek_type(), OpType::Insert(_))
+                && (priority || !matches!(other_iter.peek_type(), OpType::Insert(_)))
+            {
+                let length = iter.peek_len();
+                iter.next();
+                delta.push(Op::retain(length, None));
+            } else if matches!(other_iter.peek_type(), OpType::Insert(_)) {
+                delta.push(other_iter.next().unwrap());
+            } else {
+                let length = min(iter.peek_len(), other_iter.peek_len());
+                let self_op = iter.next_len(length);
+                let other_op = other_iter.next_len(length);
+                if self_op.is_delete() {
+                    // Our delete either makes their delete redundant or removes their retain
+                    continue;
+                } else if other_op.is_delete() {
+                    delta.push(other_op);
+                } else {
+                    // We retain either their retain or insert
+                    delta.push(Op::retain(
+                        length,
+                        AttributesMap::transform(
+                            self_op.attributes().cloned().unwrap_or_default(),
+                            other_op.attributes().cloned().unwrap_or_default(),
+                            priority,
+                        ),
+                    ));
+                }
+            }
         }
-        Ok(())
+        delta.chop();
+        delta
     }
-}
 
-#[cfg(test)]
-mod push_tests {
-    use serde_json::Value;
+    /// Transform an index against this [Delta]
+    ///
+    /// Returns the index of the same position once this [Delta] is applied. If `priority`
+    /// is `true`, an insert at `index` is considered to happen after the position, which is
+    /// then left unchanged.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use quill_delta_rs::Delta;
+    ///
+    /// let change = Delta::new().retain(2, None).insert("A", None).delete(3);
+    /// assert_eq!(1, change.transform_position(1, false));
+    /// assert_eq!(3, change.transform_position(2, false));
+    /// assert_eq!(2, change.transform_position(2, true));
+    /// assert_eq!(3, change.transform_position(4, false));
+    /// assert_eq!(4, change.transform_position(6, false));
+    /// ```
+    pub fn transform_position(&self, index: usize, priority: bool) -> usize {
+        let mut iter = Iterator::from(self.ops.clone());
+        let mut index = index;
+        let mut offset = 0;
+        while iter.has_next() && offset <= index {
+            let length = iter.peek_len();
+            let next_op = iter.next().unwrap();
+            if next_op.is_delete() {
+                index -= min(length, index - offset);
+                continue;
+            } else if next_op.is_insert() && (offset < index || !priority) {
+                index += length;
+            }
+            offset += length;
+        }
+        index
+    }
 
-    use crate::{
-        Op, {AttributesMap, attributes},
-    };
+    /// Get the formats common to a range of a document [Delta]
+    ///
+    /// Returns the inline formats shared by every character of the range other than
+    /// newlines, and the line formats shared by every line the range touches. If `length`
+    /// is 0, inline formats are the ones of the character before `index` on the same line.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use quill_delta_rs::{Delta, Op, {attributes, AttributesMap}};
+    ///
+    /// let delta = Delta::from(vec![
+    ///     Op::insert("Hello ", Some(attributes!("bold" => true))),
+    ///     Op::insert("World", Some(attributes!("bold" => true, "italic" => true))),
+    ///     Op::insert("\n", Some(attributes!("header" => 1))),
+    /// ]);
+    /// let (inline, line) = delta.get_format(3, 5);
+    /// assert_eq!(attributes!("bold" => true), inline);
+    /// assert_eq!(attributes!("header" => 1), line);
+    /// ```
+    pub fn get_format(&self, index: usize, length: usize) -> (AttributesMap, AttributesMap) {
+        let intersect = |common: Option<AttributesMap>, attributes: AttributesMap| match common {
+            None => Some(attributes),
+            Some(common) => Some(AttributesMap::intersect(common, attributes).unwrap_or_default()),
+        };
+
+        let (start, end) = if length == 0 {
+            (index.saturating_sub(1), index)
+        } else {
+            (index, index + length)
+        };
+        let mut inline = None;
+        for op in self.slice(start, Some(end)).ops {
+            if !op.is_insert()
+                || (op.is_text_insert() && op.value_as_string().chars().all(|c| c == '\n'))
+            {
+                continue;
+            }
+            inline = intersect(inline, op.attributes().cloned().unwrap_or_default());
+        }
 
-    use super::Delta;
+        let mut line = None;
+        for (_, attributes) in self.line_ends(index, length) {
+            line = intersect(line, attributes);
+        }
 
-    #[test]
-    fn from_ops() {
-        let delta = Delta::from(vec![
-            Op::retain(2, None),
-            Op::delete(3),
-            Op::insert("a", None),
-        ]);
-        assert_eq!(3, delta.ops().len());
+        (inline.unwrap_or_default(), line.unwrap_or_default())
     }
 
-    #[test]
-    fn push_on_empty() {
-        let mut delta = Delta::new();
-        delta.push(Op::insert(Value::from("test"), None));
-        assert_eq!(1, delta.ops().len());
+    /// Build the change applying inline formats to a range of a document [Delta]
+    ///
+    /// Newlines of the range are left untouched, while embeds are formatted like text. The
+    /// change is meant to be composed with this [Delta].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use quill_delta_rs::{Delta, Op, {attributes, AttributesMap}};
+    ///
+    /// let doc = Delta::from(vec![Op::insert("Hello\nWorld\n", None)]);
+    /// let change = doc.format_text(3, 5, attributes!("bold" => true));
+    /// assert_eq!(
+    ///     Delta::new()
+    ///         .retain(3, None)
+    ///         .retain(2, Some(attributes!("bold" => true)))
+    ///         .retain(1, None)
+    ///         .retain(2, Some(attributes!("bold" => true))),
+    ///     change
+    /// );
+    /// ```
+    pub fn format_text(&self, index: usize, length: usize, attributes: AttributesMap) -> Delta {
+        self.format_range(index, length, |_, newline| {
+            if newline {
+                AttributesMap::new()
+            } else {
+                attributes.clone()
+            }
+        })
     }
 
-    #[test]
-    fn push_insert_on_delete() {
-        let mut delta = Delta::new().delete(3);
-        delta.push(Op::insert("b", None));
-        assert_eq!(2, delta.ops().len());
-        assert_eq!(Op::insert("b", None), delta.ops()[0],);
+    /// Build the change applying line formats to every line a range of a document [Delta]
+    /// touches
+    ///
+    /// Only the newlines ending those lines are formatted. If `length` is 0, the line
+    /// containing `index` is formatted. The change is meant to be composed with this [Delta].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use quill_delta_rs::{Delta, Op, {attributes, AttributesMap}};
+    ///
+    /// let doc = Delta::from(vec![Op::insert("One\nTwo\nThree\n", None)]);
+    /// let change = doc.format_line(5, 4, attributes!("list" => "bullet"));
+    /// assert_eq!(
+    ///     Delta::new()
+    ///         .retain(7, None)
+    ///         .retain(1, Some(attributes!("list" => "bullet")))
+    ///         .retain(5, None)
+    ///         .retain(1, Some(attributes!("list" => "bullet"))),
+    ///     change
+    /// );
+    /// ```
+    pub fn format_line(&self, index: usize, length: usize, attributes: AttributesMap) -> Delta {
+        self.format_line_ends(index, length, |_| attributes.clone())
     }
 
-    #[test]
-    fn push_insert_on_insert_with_matching_attributes_plus_delete() {
-        let mut delta = Delta::new()
-            .insert("a", Some(attributes!("bold" => true)))
-            .delete(3);
-        delta.push(Op::insert("b", Some(attributes!("bold" => true))));
-        assert_eq!(2, delta.ops().len());
-        assert_eq!(
-            Op::insert("ab", Some(attributes!("bold" => true))),
-            delta.ops()[0],
-        );
+    /// Build the change removing every inline format of a range of a document [Delta] and
+    /// every line format of the lines it touches
+    ///
+    /// The change is meant to be composed with this [Delta].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use quill_delta_rs::{Delta, Op, {attributes, AttributesMap}};
+    /// use serde_json::Value;
+    ///
+    /// let doc = Delta::from(vec![
+    ///     Op::insert("Title", Some(attributes!("bold" => true))),
+    ///     Op::insert("\n", Some(attributes!("header" => 1))),
+    /// ]);
+    /// let change = doc.remove_format(0, 2);
+    /// assert_eq!(
+    ///     Delta::new()
+    ///         .retain(2, Some(attributes!("bold" => Value::Null)))
+    ///         .retain(3, None)
+    ///         .retain(1, Some(attributes!("header" => Value::Null))),
+    ///     change
+    /// );
+    /// assert_eq!(
+    ///     Delta::from(vec![
+    ///         Op::insert("Ti", None),
+    ///         Op::insert("tle", Some(attributes!("bold" => true))),
+    ///         Op::insert("\n", None),
+    ///     ]),
+    ///     doc.compose(&change)
+    /// );
+    /// ```
+    pub fn remove_format(&self, index: usize, length: usize) -> Delta {
+        let remove = |attributes: &AttributesMap| -> AttributesMap {
+            attributes
+                .into_iter()
+                .map(|(key, _)| (key, Value::Null))
+                .collect()
+        };
+        let inline = self.format_range(index, length, |attributes, newline| {
+            if newline {
+                AttributesMap::new()
+            } else {
+                remove(attributes)
+            }
+        });
+        let lines = self.format_line_ends(index, length, remove);
+        inline.compose(&lines)
     }
 
-    #[test]
-    fn push_insert_on_insert_with_different_attributes_plus_delete() {
-        let mut delta = Delta::new()
-            .insert("a", Some(attributes!("bold" => true)))
-            .delete(3);
-        delta.push(Op::insert("b", Some(attributes!("italic" => true))));
-        assert_eq!(3, delta.ops().len());
-        assert_eq!(
-            Op::insert("a", Some(attributes!("bold" => true))),
-            delta.ops()[0],
-        );
-        assert_eq!(
-            Op::insert("b", Some(attributes!("italic" => true))),
-            delta.ops()[1],
-        );
-        assert_eq!(Op::delete(3), delta.ops()[2],);
+    /// Build the change inserting text at `index` of a document [Delta]
+    ///
+    /// If `attributes` is [None], the text inherits the inline formats of the character before
+    /// `index` on the same line. Inserted newlines split the line containing `index` and carry
+    /// its line formats. `index` is clamped to the length of the [Delta].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use quill_delta_rs::{Delta, Op, {attributes, AttributesMap}};
+    ///
+    /// let doc = Delta::from(vec![
+    ///     Op::insert("Hello", Some(attributes!("bold" => true))),
+    ///     Op::insert("\n", Some(attributes!("header" => 1))),
+    /// ]);
+    /// let change = doc.insert_text(5, " World", None);
+    /// assert_eq!(
+    ///     Delta::from(vec![
+    ///         Op::insert("Hello World", Some(attributes!("bold" => true))),
+    ///         Op::insert("\n", Some(attributes!("header" => 1))),
+    ///     ]),
+    ///     doc.compose(&change)
+    /// );
+    /// ```
+    pub fn insert_text(
+        &self,
+        index: usize,
+        text: &str,
+        attributes: Option<AttributesMap>,
+    ) -> Delta {
+        let index = min(index, self.len());
+        let (inline, line) = self.get_format(index, 0);
+        let mut change = Delta::new();
+        if index > 0 {
+            change.push(Op::retain(index, None));
+        }
+        change.push_text(text, attributes.unwrap_or(inline), line);
+        change
     }
 
-    #[test]
-    fn push_consecutive_delete() {
-        let mut delta = Delta::new().delete(2);
-        delta.push(Op::delete(3));
-        assert_eq!(1, delta.ops().len());
-        assert_eq!(Op::delete(5), delta.ops()[0]);
+    /// Build the change deleting a range of a document [Delta]
+    ///
+    /// The range is clamped to the length of the [Delta].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use quill_delta_rs::{Delta, Op};
+    ///
+    /// let doc = Delta::from(vec![Op::insert("Hello World\n", None)]);
+    /// let change = doc.delete_text(5, 6);
+    /// assert_eq!(Delta::new().retain(5, None).delete(6), change);
+    /// assert_eq!(
+    ///     Delta::from(vec![Op::insert("Hello\n", None)]),
+    ///     doc.compose(&change)
+    /// );
+    /// ```
+    pub fn delete_text(&self, index: usize, length: usize) -> Delta {
+        let length = min(length, self.len().saturating_sub(index));
+        let mut change = Delta::new();
+        if index > 0 && length > 0 {
+            change.push(Op::retain(index, None));
+        }
+        if length > 0 {
+            change.push(Op::delete(length));
+        }
+        change
     }
 
-    #[test]
-    fn push_consecutive_text() {
-        let mut delta = Delta::new().insert("a", None);
-        delta.push(Op::insert("b", None));
-        assert_eq!(1, delta.ops().len());
-        assert_eq!(Op::insert("ab", None), delta.ops()[0]);
+    /// Build the change replacing a range of a document [Delta] with text
+    ///
+    /// Each character of the text takes the inline formats of the replaced character at the
+    /// same position, newlines and embeds left aside, and characters past the end of the
+    /// replaced text take the formats of its last character. If no text is replaced, the text
+    /// inherits its formats as in [Delta::insert_text]. Inserted newlines carry the line formats
+    /// of the line containing `index`, and so does the line joining the lines of a range
+    /// spanning newlines, as in Quill. The range is clamped to the length of the [Delta].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use quill_delta_rs::{Delta, Op, {attributes, AttributesMap}};
+    ///
+    /// let doc = Delta::from(vec![
+    ///     Op::insert("Hello ", None),
+    ///     Op::insert("World", Some(attributes!("italic" => true))),
+    ///     Op::insert("\n", None),
+    /// ]);
+    /// let change = doc.replace_range(6, 5, "Earth");
+    /// assert_eq!(
+    ///     Delta::from(vec![
+    ///         Op::insert("Hello ", None),
+    ///         Op::insert("Earth", Some(attributes!("italic" => true))),
+    ///         Op::insert("\n", None),
+    ///     ]),
+    ///     doc.compose(&change)
+    /// );
+    /// ```
+    pub fn replace_range(&self, index: usize, length: usize, text: &str) -> Delta {
+        let index = min(index, self.len());
+        let length = min(length, self.len() - index);
+        if length == 0 {
+            return self.insert_text(index, text, None);
+        }
+        let mut formats = self.slice(index, Some(index + length)).inline_formats();
+        if formats.is_empty() {
+            formats.push((1, self.get_format(index, 0).0));
+        }
+        // Lines touched by the range, and the one after it if the range ends with a newline
+        let lines = self.line_ends(index, length + 1);
+        let line = lines
+            .first()
+            .map(|(_, line)| line.clone())
+            .unwrap_or_default();
+
+        let mut change = Delta::new();
+        if index > 0 {
+            change.push(Op::retain(index, None));
+        }
+        change.push_formatted(text, &formats, line.clone());
+        change.push(Op::delete(length));
+        if lines.len() > 1
+            && let Some((newline, attributes)) = lines.last()
+            && *newline < self.len()
+            && let Some(attributes) = AttributesMap::diff(attributes.clone(), line)
+        {
+            if *newline > index + length {
+                change.push(Op::retain(newline - index - length, None));
+            }
+            change.push(Op::retain(1, Some(attributes)));
+        }
+        change
     }
 
-    #[test]
-    fn push_consecutive_text_matching_attributes() {
-        let mut delta = Delta::new().insert("a", Some(attributes!("bold" => true)));
-        delta.push(Op::insert("b", Some(attributes!("bold" => true))));
-        assert_eq!(1, delta.ops().len());
+    /// Push inserts of text, with `line` attributes on newlines and `inline` attributes on
+    /// other characters
+    pub(crate) fn push_text(&mut self, text: &str, inline: AttributesMap, line: AttributesMap) {
+        self.push_formatted(text, &[(1, inline)], line);
+    }
+
+    /// Push inserts of text, with `line` attributes on newlines and the attributes of
+    /// `formats` on other characters
+    ///
+    /// `formats` are runs of characters as returned by [Delta::inline_formats]; the last run
+    /// extends to the end of the text.
+    pub(crate) fn push_formatted(
+        &mut self,
+        text: &str,
+        formats: &[(usize, AttributesMap)],
+        line: AttributesMap,
+    ) {
+        let non_empty = |attributes: &AttributesMap| {
+            if attributes.is_empty() {
+                None
+            } else {
+                Some(attributes.clone())
+            }
+        };
+        let mut formats = formats.iter();
+        let mut format = formats.next();
+        let mut left = format.map_or(0, |(count, _)| *count);
+        let empty = AttributesMap::new();
+        for part in text.split_inclusive('\n') {
+            let (mut content, newline) = match part.strip_suffix('\n') {
+                Some(content) => (content, true),
+                None => (part, false),
+            };
+            while !content.is_empty() {
+                let attributes = format.map_or(&empty, |(_, attributes)| attributes);
+                let last = formats.as_slice().is_empty();
+                let end = match content.char_indices().nth(left) {
+                    Some((end, _)) if !last => end,
+                    _ => content.len(),
+                };
+                let (chunk, rest) = content.split_at(end);
+                self.push(Op::insert(chunk, non_empty(attributes)));
+                content = rest;
+                if !last {
+                    left -= chunk.chars().count();
+                    if left == 0 {
+                        format = formats.next();
+                        left = format.map_or(0, |(count, _)| *count);
+                    }
+                }
+            }
+            if newline {
+                self.push(Op::insert("\n", non_empty(&line)));
+            }
+        }
+    }
+
+    /// Runs of the inline formats of the characters of a document [Delta], as numbers of
+    /// characters and their attributes, newlines and embeds left aside
+    pub(crate) fn inline_formats(&self) -> Vec<(usize, AttributesMap)> {
+        let mut formats: Vec<(usize, AttributesMap)> = Vec::new();
+        for op in &self.ops {
+            if !op.is_text_insert() {
+                continue;
+            }
+            let count = op.value_as_string().chars().filter(|c| *c != '\n').count();
+            let attributes = op.attributes().cloned().unwrap_or_default();
+            match formats.last_mut() {
+                _ if count == 0 => {}
+                Some((last, last_attributes)) if *last_attributes == attributes => *last += count,
+                _ => formats.push((count, attributes)),
+            }
+        }
+        formats
+    }
+
+    /// Build a change retaining a range of a document [Delta], where each part of the range
+    /// is retained with the attributes returned by `attributes`.
+    ///
+    /// `attributes` is called with the attributes of the part and whether it is a newline.
+    fn format_range<F>(&self, index: usize, length: usize, mut attributes: F) -> Delta
+    where
+        F: FnMut(&AttributesMap, bool) -> AttributesMap,
+    {
+        let mut change = Delta::new();
+        let retain = |change: &mut Delta, length: usize, attributes: AttributesMap| {
+            if length > 0 {
+                change.push(Op::retain(
+                    length,
+                    if attributes.is_empty() {
+                        None
+                    } else {
+                        Some(attributes)
+                    },
+                ));
+            }
+        };
+        retain(&mut change, index, AttributesMap::new());
+        for op in self.slice(index, Some(index + length)).ops {
+            let op_attributes = op.attributes().cloned().unwrap_or_default();
+            if !op.is_text_insert() {
+                retain(&mut change, op.len(), attributes(&op_attributes, false));
+                continue;
+            }
+            for line in op.value_as_string().split_inclusive('\n') {
+                match line.strip_suffix('\n') {
+                    Some(content) => {
+                        retain(
+                            &mut change,
+                            content.chars().count(),
+                            attributes(&op_attributes, false),
+                        );
+                        retain(&mut change, 1, attributes(&op_attributes, true));
+                    }
+                    None => retain(
+                        &mut change,
+                        line.chars().count(),
+                        attributes(&op_attributes, false),
+                    ),
+                }
+            }
+        }
+        change.chop();
+        change
+    }
+
+    /// Build a change retaining the newlines of the lines of a document [Delta] that a range
+    /// touches with the attributes returned by `attributes`.
+    ///
+    /// `attributes` is called with the attributes of the newline.
+    fn format_line_ends<F>(&self, index: usize, length: usize, mut attributes: F) -> Delta
+    where
+        F: FnMut(&AttributesMap) -> AttributesMap,
+    {
+        let mut change = Delta::new();
+        let mut change_len = 0;
+        let end = self.len();
+        for (newline, newline_attributes) in self.line_ends(index, length) {
+            if newline >= end {
+                break;
+            }
+            if newline > change_len {
+                change.push(Op::retain(newline - change_len, None));
+            }
+            let attributes = attributes(&newline_attributes);
+            change.push(Op::retain(
+                1,
+                if attributes.is_empty() {
+                    None
+                } else {
+                    Some(attributes)
+                },
+            ));
+            change_len = newline + 1;
+        }
+        change.chop();
+        change
+    }
+
+    /// Positions and attributes of the newlines ending the lines of a document [Delta] that a
+    /// range touches, or of the line containing `index` if `length` is 0.
+    ///
+    /// A last line without a trailing newline ends at the length of the [Delta], with empty
+    /// attributes.
+    fn line_ends(&self, index: usize, length: usize) -> Vec<(usize, AttributesMap)> {
+        let mut line_ends = Vec::new();
+        let mut line_start = 0;
+        self.each_line(|content, attributes, _| {
+            let newline = line_start + content.len();
+            let touched = if length == 0 {
+                line_start <= index
+            } else {
+                line_start < index + length
+            };
+            if !touched {
+                return false;
+            }
+            if newline >= index {
+                line_ends.push((newline, attributes.clone()));
+            }
+            line_start = newline + 1;
+            true
+        });
+        line_ends
+    }
+
+    /// Make this [Delta] conform to a [Schema]
+    ///
+    /// - Attribute values the schema does not allow are removed.
+    /// - [Scope::Inline] and [Scope::Embed] attributes are removed from newlines.
+    /// - [Scope::Block] attributes found on other characters, embeds included, are moved to the
+    ///   newline ending their line, unless it already has a value for that attribute, and are
+    ///   otherwise removed. A line is ended by the next newline inserted before any retain or
+    ///   delete operation.
+    /// - Embeds whose type is registered with a scope other than [Scope::Embed], or whose value
+    ///   is not allowed, are removed.
+    ///
+    /// Keys that are not registered in the schema are left untouched. Retain and delete
+    /// operations apply to unknown content and only have their attribute values checked.
+    /// Empty operations, such as a deserialized `{"retain": 0}`, are removed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use quill_delta_rs::{Delta, Op, Schema, {attributes, AttributesMap}};
+    ///
+    /// let delta = Delta::from(vec![
+    ///     Op::insert("Title", Some(attributes!("header" => 1, "bold" => true))),
+    ///     Op::insert("\n", Some(attributes!("bold" => true))),
+    /// ]);
+    /// let expected = Delta::from(vec![
+    ///     Op::insert("Title", Some(attributes!("bold" => true))),
+    ///     Op::insert("\n", Some(attributes!("header" => 1))),
+    /// ]);
+    /// assert_eq!(expected, delta.conform(&Schema::quill()));
+    /// ```
+    pub fn conform(&self, schema: &Schema) -> Delta {
+        let keep = |attributes: Option<&AttributesMap>, scopes: &[Scope]| -> AttributesMap {
+            attributes
+                .into_iter()
+                .flatten()
+                .filter(|(key, value)| {
+                    schema.allows(key, value)
+                        && schema
+                            .scope(key)
+                            .is_none_or(|scope| scopes.contains(&scope))
+                })
+                .collect()
+        };
+        let non_empty = |attributes: AttributesMap| {
+            if attributes.is_empty() {
+                None
+            } else {
+                Some(attributes)
+            }
+        };
+
+        let mut conformed = Delta::new();
+        // Block attributes of inline content, waiting for the newline ending the line
+        let mut pending = AttributesMap::new();
+        for op in self.ops.iter().filter(|op| !op.is_empty()) {
+            match op.kind() {
+                OpType::Insert(Value::String(text)) => {
+                    let inline = keep(op.attributes(), &[Scope::Inline]);
+                    let block = keep(op.attributes(), &[Scope::Block]);
+                    for line in text.split_inclusive('\n') {
+                        let (content, newline) = match line.strip_suffix('\n') {
+                            Some(content) => (content, true),
+                            None => (line, false),
+                        };
+                        if !content.is_empty() {
+                            conformed.push(Op::insert(content, non_empty(inline.clone())));
+                            for (key, value) in &block {
+                                if schema.contains(&key) && !value.is_null() {
+                                    pending.insert(key, value);
+                                }
+                            }
+                        }
+                        if newline {
+                            let mut attributes = block.clone();
+                            for (key, value) in &pending {
+                                if attributes.get(&key).is_none() {
+                                    attributes.insert(key, value);
+                                }
+                            }
+                            pending = AttributesMap::new();
+                            conformed.push(Op::insert("\n", non_empty(attributes)));
+                        }
+                    }
+                }
+                OpType::Insert(embed) => {
+                    let allowed = embed.as_object().is_none_or(|embed| {
+                        embed.iter().all(|(key, value)| {
+                            schema.allows(key, value)
+                                && schema.scope(key).is_none_or(|scope| scope == Scope::Embed)
+                        })
+                    });
+                    if !allowed {
+                        continue;
+                    }
+                    let inline = keep(op.attributes(), &[Scope::Inline]);
+                    conformed.push(Op::insert_any(embed.clone(), non_empty(inline)));
+                    for (key, value) in keep(op.attributes(), &[Scope::Block]) {
+                        if schema.contains(&key) && !value.is_null() {
+                            pending.insert(key, value);
+                        }
+                    }
+                }
+                OpType::Retain(length) => {
+                    let attributes = keep(op.attributes(), &[Scope::Inline, Scope::Block]);
+                    conformed.push(Op::retain(*length, non_empty(attributes)));
+                    pending = AttributesMap::new();
+                }
+                OpType::Delete(_) => {
+                    conformed.push(op.clone());
+                    pending = AttributesMap::new();
+                }
+            }
+        }
+        conformed
+    }
+}
+
+impl From<Vec<Op>> for Delta {
+    fn from(value: Vec<Op>) -> Self {
+        Delta { ops: value }
+    }
+}
+
+impl Display for Delta {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for op in &self.ops {
+            f.write_fmt(format_args!("{op}\n"))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod push_tests {
+    use serde_json::Value;
+
+    use crate::{AttributesMap, Op};
+
+    use super::Delta;
+
+    #[test]
+    fn from_ops() {
+        let delta = Delta::from(vec![
+            Op::retain(2, None),
+            Op::delete(3),
+            Op::insert("a", None),
+        ]);
+        assert_eq!(3, delta.ops().len());
+    }
+
+    #[test]
+    fn push_on_empty() {
+        let mut delta = Delta::new();
+        delta.push(Op::insert(Value::from("test"), None));
+        assert_eq!(1, delta.ops().len());
+    }
+
+    #[test]
+    fn push_insert_on_delete() {
+        let mut delta = Delta::new().delete(3);
+        delta.push(Op::insert("b", None));
+        assert_eq!(2, delta.ops().len());
+        assert_eq!(Op::insert("b", None), delta.ops()[0],);
+    }
+
+    #[test]
+    fn push_insert_on_insert_with_matching_attributes_plus_delete() {
+        let mut delta = Delta::new()
+            .insert("a", Some(attributes!("bold" => true)))
+            .delete(3);
+        delta.push(Op::insert("b", Some(attributes!("bold" => true))));
+        assert_eq!(2, delta.ops().len());
+        assert_eq!(
+            Op::insert("ab", Some(attributes!("bold" => true))),
+            delta.ops()[0],
+        );
+    }
+
+    #[test]
+    fn push_insert_on_insert_with_different_attributes_plus_delete() {
+        let mut delta = Delta::new()
+            .insert("a", Some(attributes!("bold" => true)))
+            .delete(3);
+        delta.push(Op::insert("b", Some(attributes!("italic" => true))));
+        assert_eq!(3, delta.ops().len());
+        assert_eq!(
+            Op::insert("a", Some(attributes!("bold" => true))),
+            delta.ops()[0],
+        );
+        assert_eq!(
+            Op::insert("b", Some(attributes!("italic" => true))),
+            delta.ops()[1],
+        );
+        assert_eq!(Op::delete(3), delta.ops()[2],);
+    }
+
+    #[test]
+    fn push_consecutive_delete() {
+        let mut delta = Delta::new().delete(2);
+        delta.push(Op::delete(3));
+        assert_eq!(1, delta.ops().len());
+        assert_eq!(Op::delete(5), delta.ops()[0]);
+    }
+
+    #[test]
+    fn push_consecutive_text() {
+        let mut delta = Delta::new().insert("a", None);
+        delta.push(Op::insert("b", None));
+        assert_eq!(1, delta.ops().len());
+        assert_eq!(Op::insert("ab", None), delta.ops()[0]);
+    }
+
+    #[test]
+    fn push_consecutive_text_matching_attributes() {
+        let mut delta = Delta::new().insert("a", Some(attributes!("bold" => true)));
+        delta.push(Op::insert("b", Some(attributes!("bold" => true))));
+        assert_eq!(1, delta.ops().len());
         assert_eq!(
             Op::insert("ab", Some(attributes!("bold" => true))),
             delta.ops()[0],
@@ -654,9 +1508,7 @@ mod push_tests {
 mod helpers_tests {
     use serde_json::json;
 
-    use crate::{
-        Op, {AttributesMap, attributes},
-    };
+    use crate::{AttributesMap, Op};
 
     use super::Delta;
 
@@ -745,6 +1597,16 @@ mod helpers_tests {
         assert_eq!(1, delta.change_len());
     }
 
+    #[test]
+    fn non_ascii_lengths() {
+        let delta = Delta::new().insert("Grüße 世界", None).delete(2);
+        assert_eq!(10, delta.len());
+        assert_eq!(6, delta.change_len());
+        assert_eq!(Delta::new().insert("ße 世", None), delta.slice(3, Some(7)));
+        let change = Delta::new().insert("Grüße ", None);
+        assert_eq!(8, change.transform_position(2, false));
+    }
+
     #[test]
     fn plain_text() {
         let delta = Delta::new()
@@ -817,9 +1679,7 @@ mod compose_tests {
 
     use serde_json::Value;
 
-    use crate::{
-        Op, {AttributesMap, attributes},
-    };
+    use crate::{AttributesMap, Op};
 
     use super::Delta;
 
@@ -926,6 +1786,23 @@ mod compose_tests {
         assert_eq!(expected, a.compose(&b))
     }
 
+    #[test]
+    fn retain_null_retain() {
+        let a = Delta::from(vec![Op::retain(
+            2,
+            Some(attributes!("bold" => None::<&str>)),
+        )]);
+        let b = Delta::from(vec![Op::retain(1, Some(attributes!("color" => "red")))]);
+        let expected = Delta::from(vec![
+            Op::retain(
+                1,
+                Some(attributes!("bold" => None::<&str>, "color" => "red")),
+            ),
+            Op::retain(1, Some(attributes!("bold" => None::<&str>))),
+        ]);
+        assert_eq!(expected, a.compose(&b))
+    }
+
     #[test]
     fn retain_delete() {
         let a = Delta::from(vec![Op::retain(1, Some(attributes!("color" => "blue")))]);
@@ -1003,6 +1880,25 @@ mod compose_tests {
         assert_eq!(Delta::from(vec![Op::insert("A", None)]), a.compose(&b));
     }
 
+    #[test]
+    fn retain_removing_format_then_retain() {
+        let a = Delta::from(vec![Op::retain(
+            1,
+            Some(attributes!("bold" => Value::Null)),
+        )]);
+        let b = Delta::from(vec![Op::retain(1, Some(attributes!("italic" => true)))]);
+        let expected = Delta::from(vec![Op::retain(
+            1,
+            Some(attributes!("bold" => Value::Null, "italic" => true)),
+        )]);
+        assert_eq!(expected, a.compose(&b));
+        let document = Delta::from(vec![Op::insert("A", Some(attributes!("bold" => true)))]);
+        assert_eq!(
+            document.compose(&a).compose(&b),
+            document.compose(&a.compose(&b))
+        );
+    }
+
     #[test]
     fn remove_all_embed_attributes() {
         let a = Delta::from(vec![Op::insert(
@@ -1118,9 +2014,7 @@ mod compose_tests {
 #[cfg(test)]
 mod invert_tests {
 
-    use crate::{
-        Op, {AttributesMap, attributes},
-    };
+    use crate::{AttributesMap, Op};
 
     use super::Delta;
 
@@ -1211,3 +2105,1094 @@ mod invert_tests {
         assert_eq!(base, base.compose(&delta).compose(&inverted))
     }
 }
+
+#[cfg(test)]
+mod diff_tests {
+    use serde_json::json;
+
+    use crate::{AttributesMap, Op};
+
+    use super::Delta;
+
+    #[test]
+    fn insert() {
+        let a = Delta::new().insert("A", None);
+        let b = Delta::new().insert("AB", None);
+        assert_eq!(Delta::new().retain(1, None).insert("B", None), a.diff(&b));
+    }
+
+    #[test]
+    fn delete() {
+        let a = Delta::new().insert("AB", None);
+        let b = Delta::new().insert("A", None);
+        assert_eq!(Delta::new().retain(1, None).delete(1), a.diff(&b));
+    }
+
+    #[test]
+    fn retain() {
+        let a = Delta::new().insert("A", None);
+        let b = Delta::new().insert("A", None);
+        assert_eq!(Delta::new(), a.diff(&b));
+    }
+
+    #[test]
+    fn format() {
+        let a = Delta::new().insert("A", None);
+        let b = Delta::new().insert("A", Some(attributes!("bold" => true)));
+        assert_eq!(
+            Delta::new().retain(1, Some(attributes!("bold" => true))),
+            a.diff(&b)
+        );
+    }
+
+    #[test]
+    fn object_attributes() {
+        let a = Delta::new().insert(
+            "A",
+            Some(attributes!("font" => json!({"family": "Helvetica", "size": "15px"}))),
+        );
+        let b = a.clone();
+        assert_eq!(Delta::new(), a.diff(&b));
+    }
+
+    #[test]
+    fn embed_integer_match() {
+        let a = Delta::from(vec![Op::insert(1, None)]);
+        let b = Delta::from(vec![Op::insert(1, None)]);
+        assert_eq!(Delta::new(), a.diff(&b));
+    }
+
+    #[test]
+    fn embed_integer_mismatch() {
+        let a = Delta::from(vec![Op::insert(1, None)]);
+        let b = Delta::from(vec![Op::insert(2, None)]);
+        assert_eq!(
+            Delta::from(vec![Op::insert(2, None), Op::delete(1)]),
+            a.diff(&b)
+        );
+    }
+
+    #[test]
+    fn embed_object_mismatch() {
+        let a = Delta::from(vec![Op::insert(
+            json!({"image": "http://quilljs.com"}),
+            None,
+        )]);
+        let b = Delta::from(vec![Op::insert(
+            json!({"image": "http://github.com"}),
+            None,
+        )]);
+        assert_eq!(
+            Delta::from(vec![
+                Op::insert(json!({"image": "http://github.com"}), None),
+                Op::delete(1)
+            ]),
+            a.diff(&b)
+        );
+    }
+
+    #[test]
+    fn embed_false_positive() {
+        let a = Delta::from(vec![Op::insert(1, None)]);
+        let b = Delta::new().insert("\0", None);
+        assert_eq!(
+            Delta::from(vec![Op::insert("\0", None), Op::delete(1)]),
+            a.diff(&b)
+        );
+    }
+
+    #[test]
+    fn inconvenient_indexes() {
+        let a = Delta::new()
+            .insert("12", Some(attributes!("bold" => true)))
+            .insert("34", Some(attributes!("italic" => true)));
+        let b = Delta::new().insert("123", Some(attributes!("color" => "red")));
+        assert_eq!(
+            Delta::new()
+                .retain(
+                    2,
+                    Some(attributes!("bold" => serde_json::Value::Null, "color" => "red"))
+                )
+                .retain(
+                    1,
+                    Some(attributes!("italic" => serde_json::Value::Null, "color" => "red"))
+                )
+                .delete(1),
+            a.diff(&b)
+        );
+    }
+
+    #[test]
+    fn combination() {
+        let a = Delta::new()
+            .insert("Bad", Some(attributes!("color" => "red")))
+            .insert("cat", Some(attributes!("color" => "blue")));
+        let b = Delta::new()
+            .insert("Good", Some(attributes!("bold" => true)))
+            .insert("dog", Some(attributes!("italic" => true)));
+        assert_eq!(a.compose(&a.diff(&b)), b);
+    }
+
+    #[test]
+    fn multi_byte() {
+        let a = Delta::new().insert("Grüße, 世界\n", None);
+        let b = Delta::new()
+            .insert("Grüße, ", None)
+            .insert("schöne", Some(attributes!("bold" => true)))
+            .insert(" 世界!\n", None);
+        let change = a.diff(&b);
+        assert_eq!(
+            Delta::from(vec![
+                Op::retain(7, None),
+                Op::insert("schöne", Some(attributes!("bold" => true))),
+                Op::insert(" ", None),
+                Op::retain(2, None),
+                Op::insert("!", None),
+            ]),
+            change
+        );
+        assert_eq!(b, a.compose(&change));
+    }
+
+    #[test]
+    #[should_panic(expected = "non-document")]
+    fn non_document() {
+        let a = Delta::new().insert("A", None);
+        let b = Delta::new().retain(1, None).insert("B", None);
+        a.diff(&b);
+    }
+}
+
+#[cfg(test)]
+mod transform_tests {
+    use crate::{AttributesMap, Op};
+
+    use super::Delta;
+
+    #[test]
+    fn insert_insert() {
+        let a1 = Delta::new().insert("A", None);
+        let b1 = Delta::new().insert("B", None);
+        let a2 = a1.clone();
+        let b2 = b1.clone();
+        assert_eq!(
+            Delta::new().retain(1, None).insert("B", None),
+            a1.transform(&b1, true)
+        );
+        assert_eq!(Delta::new().insert("B", None), a2.transform(&b2, false));
+    }
+
+    #[test]
+    fn insert_retain() {
+        let a = Delta::new().insert("A", None);
+        let b = Delta::new().retain(1, Some(attributes!("bold" => true, "color" => "red")));
+        assert_eq!(
+            Delta::new()
+                .retain(1, None)
+                .retain(1, Some(attributes!("bold" => true, "color" => "red"))),
+            a.transform(&b, true)
+        );
+    }
+
+    #[test]
+    fn insert_delete() {
+        let a = Delta::new().insert("A", None);
+        let b = Delta::new().delete(1);
+        assert_eq!(
+            Delta::new().retain(1, None).delete(1),
+            a.transform(&b, true)
+        );
+    }
+
+    #[test]
+    fn delete_insert() {
+        let a = Delta::new().delete(1);
+        let b = Delta::new().insert("B", None);
+        assert_eq!(Delta::new().insert("B", None), a.transform(&b, true));
+    }
+
+    #[test]
+    fn delete_retain() {
+        let a = Delta::new().delete(1);
+        let b = Delta::new().retain(1, Some(attributes!("bold" => true, "color" => "red")));
+        assert_eq!(Delta::new(), a.transform(&b, true));
+    }
+
+    #[test]
+    fn delete_delete() {
+        let a = Delta::new().delete(1);
+        let b = Delta::new().delete(1);
+        assert_eq!(Delta::new(), a.transform(&b, true));
+    }
+
+    #[test]
+    fn retain_insert() {
+        let a = Delta::new().retain(1, Some(attributes!("color" => "blue")));
+        let b = Delta::new().insert("B", None);
+        assert_eq!(Delta::new().insert("B", None), a.transform(&b, true));
+    }
+
+    #[test]
+    fn retain_retain() {
+        let a1 = Delta::new().retain(1, Some(attributes!("color" => "blue")));
+        let b1 = Delta::new().retain(1, Some(attributes!("bold" => true, "color" => "red")));
+        let a2 = Delta::new().retain(1, Some(attributes!("color" => "blue")));
+        let b2 = Delta::new().retain(1, Some(attributes!("bold" => true, "color" => "red")));
+        assert_eq!(
+            Delta::new().retain(1, Some(attributes!("bold" => true))),
+            a1.transform(&b1, true)
+        );
+        assert_eq!(Delta::new(), b2.transform(&a2, true));
+    }
+
+    #[test]
+    fn retain_retain_without_priority() {
+        let a = Delta::new().retain(1, Some(attributes!("color" => "blue")));
+        let b = Delta::new().retain(1, Some(attributes!("bold" => true, "color" => "red")));
+        assert_eq!(
+            Delta::new().retain(1, Some(attributes!("bold" => true, "color" => "red"))),
+            a.transform(&b, false)
+        );
+        assert_eq!(
+            Delta::new().retain(1, Some(attributes!("color" => "blue"))),
+            b.transform(&a, false)
+        );
+    }
+
+    #[test]
+    fn retain_delete() {
+        let a = Delta::new().retain(1, Some(attributes!("color" => "blue")));
+        let b = Delta::new().delete(1);
+        assert_eq!(Delta::new().delete(1), a.transform(&b, true));
+    }
+
+    #[test]
+    fn alternating_edits() {
+        let a1 = Delta::new().retain(2, None).insert("si", None).delete(5);
+        let b1 = Delta::new()
+            .retain(1, None)
+            .insert("e", None)
+            .delete(5)
+            .retain(1, None)
+            .insert("ow", None);
+        let a2 = a1.clone();
+        let b2 = b1.clone();
+        assert_eq!(
+            Delta::new()
+                .retain(1, None)
+                .insert("e", None)
+                .delete(1)
+                .retain(2, None)
+                .insert("ow", None),
+            a1.transform(&b1, false)
+        );
+        assert_eq!(
+            Delta::new().retain(2, None).insert("si", None).delete(1),
+            b2.transform(&a2, false)
+        );
+    }
+
+    #[test]
+    fn conflicting_appends() {
+        let a = Delta::new().retain(3, None).insert("aa", None);
+        let b = Delta::new().retain(3, None).insert("bb", None);
+        assert_eq!(
+            Delta::new().retain(5, None).insert("bb", None),
+            a.transform(&b, true)
+        );
+        assert_eq!(
+            Delta::new().retain(3, None).insert("aa", None),
+            b.transform(&a, false)
+        );
+    }
+
+    #[test]
+    fn prepend_and_append() {
+        let a = Delta::new().insert("aa", None);
+        let b = Delta::new().retain(3, None).insert("bb", None);
+        assert_eq!(
+            Delta::new().retain(5, None).insert("bb", None),
+            a.transform(&b, false)
+        );
+        assert_eq!(Delta::new().insert("aa", None), b.transform(&a, false));
+    }
+
+    #[test]
+    fn trailing_deletes_with_differing_lengths() {
+        let a = Delta::new().retain(2, None).delete(1);
+        let b = Delta::new().delete(3);
+        assert_eq!(Delta::new().delete(2), a.transform(&b, false));
+        assert_eq!(Delta::new(), b.transform(&a, false));
+    }
+
+    #[test]
+    fn converges() {
+        let doc = Delta::from(vec![Op::insert("Hello World\n", None)]);
+        let a = Delta::new()
+            .retain(6, None)
+            .insert("big ", None)
+            .retain(5, Some(attributes!("bold" => true)));
+        let b = Delta::new().retain(3, None).delete(5).insert("p", None);
+        assert_eq!(
+            doc.compose(&a).compose(&a.transform(&b, true)),
+            doc.compose(&b).compose(&b.transform(&a, false))
+        );
+    }
+}
+
+#[cfg(test)]
+mod transform_position_tests {
+    use crate::Op;
+
+    use super::Delta;
+
+    #[test]
+    fn insert_before_position() {
+        let delta = Delta::from(vec![Op::insert("A", None)]);
+        assert_eq!(3, delta.transform_position(2, false));
+    }
+
+    #[test]
+    fn insert_after_position() {
+        let delta = Delta::from(vec![Op::retain(2, None), Op::insert("A", None)]);
+        assert_eq!(1, delta.transform_position(1, false));
+    }
+
+    #[test]
+    fn insert_at_position() {
+        let delta = Delta::from(vec![Op::retain(2, None), Op::insert("A", None)]);
+        assert_eq!(2, delta.transform_position(2, true));
+        assert_eq!(3, delta.transform_position(2, false));
+    }
+
+    #[test]
+    fn delete_before_position() {
+        let delta = Delta::from(vec![Op::delete(2)]);
+        assert_eq!(2, delta.transform_position(4, false));
+    }
+
+    #[test]
+    fn delete_after_position() {
+        let delta = Delta::from(vec![Op::retain(4, None), Op::delete(2)]);
+        assert_eq!(2, delta.transform_position(2, false));
+    }
+
+    #[test]
+    fn delete_across_position() {
+        let delta = Delta::from(vec![Op::retain(1, None), Op::delete(4)]);
+        assert_eq!(1, delta.transform_position(2, false));
+    }
+
+    #[test]
+    fn insert_and_delete_before_position() {
+        let delta = Delta::from(vec![
+            Op::retain(2, None),
+            Op::insert("A", None),
+            Op::delete(2),
+        ]);
+        assert_eq!(3, delta.transform_position(4, false));
+    }
+
+    #[test]
+    fn insert_after_delete_across_position() {
+        let delta = Delta::from(vec![
+            Op::retain(2, None),
+            Op::delete(4),
+            Op::insert("A", None),
+        ]);
+        assert_eq!(3, delta.transform_position(4, false));
+    }
+
+    #[test]
+    fn delete_before_and_across_position() {
+        let delta = Delta::from(vec![Op::delete(1), Op::retain(1, None), Op::delete(4)]);
+        assert_eq!(1, delta.transform_position(4, false));
+    }
+}
+
+#[cfg(test)]
+mod conform_tests {
+    use serde_json::{Value, json};
+
+    use crate::{AttributesMap, Op, Schema};
+
+    use super::Delta;
+
+    #[test]
+    fn keeps_valid_document() {
+        let delta = Delta::from(vec![
+            Op::insert("Title", Some(attributes!("bold" => true))),
+            Op::insert("\n", Some(attributes!("header" => 1))),
+            Op::insert(json!({"image": "https://quilljs.com/logo.png"}), None),
+            Op::insert("\n", None),
+        ]);
+        assert_eq!(delta, delta.conform(&Schema::quill()));
+    }
+
+    #[test]
+    fn relocates_block_format() {
+        let delta = Delta::from(vec![
+            Op::insert("Hello ", Some(attributes!("header" => 2))),
+            Op::insert("World\nNext\n", None),
+        ]);
+        let expected = Delta::from(vec![
+            Op::insert("Hello World", None),
+            Op::insert("\n", Some(attributes!("header" => 2))),
+            Op::insert("Next\n", None),
+        ]);
+        assert_eq!(expected, delta.conform(&Schema::quill()));
+    }
+
+    #[test]
+    fn removes_empty_ops() {
+        let delta: Delta = serde_json::from_value(json!({"ops": [
+            {"insert": "hi"},
+            {"retain": 0, "attributes": {"bold": true}},
+            {"delete": 0},
+            {"insert": ""},
+            {"insert": "\n", "attributes": {"header": 1}},
+        ]}))
+        .unwrap();
+        assert_eq!(
+            Delta::from(vec![
+                Op::insert("hi", None),
+                Op::insert("\n", Some(attributes!("header" => 1))),
+            ]),
+            delta.conform(&Schema::quill())
+        );
+    }
+
+    #[test]
+    fn filters_embed_attributes() {
+        let delta = Delta::from(vec![
+            Op::insert_any(
+                json!({"image": "a.png"}),
+                Some(attributes!("header" => 1, "link" => "https://quilljs.com", "bold" => "no")),
+            ),
+            Op::insert("\n", None),
+        ]);
+        let expected = Delta::from(vec![
+            Op::insert_any(
+                json!({"image": "a.png"}),
+                Some(attributes!("link" => "https://quilljs.com")),
+            ),
+            Op::insert("\n", Some(attributes!("header" => 1))),
+        ]);
+        assert_eq!(expected, delta.conform(&Schema::quill()));
+    }
+
+    #[test]
+    fn block_format_does_not_cross_retain_or_delete() {
+        let delta = Delta::from(vec![
+            Op::insert("x", Some(attributes!("header" => 1))),
+            Op::retain(10, None),
+            Op::insert("\n", None),
+            Op::insert("y", Some(attributes!("list" => "bullet"))),
+            Op::delete(2),
+            Op::insert("\n", None),
+        ]);
+        let expected = Delta::new()
+            .insert("x", None)
+            .retain(10, None)
+            .insert("\ny\n", None)
+            .delete(2);
+        assert_eq!(expected, delta.conform(&Schema::quill()));
+    }
+
+    #[test]
+    fn block_format_of_newline_wins() {
+        let delta = Delta::from(vec![
+            Op::insert("Item", Some(attributes!("list" => "bullet"))),
+            Op::insert("\n", Some(attributes!("list" => "ordered"))),
+        ]);
+        let expected = Delta::from(vec![
+            Op::insert("Item", None),
+            Op::insert("\n", Some(attributes!("list" => "ordered"))),
+        ]);
+        assert_eq!(expected, delta.conform(&Schema::quill()));
+    }
+
+    #[test]
+    fn drops_block_format_without_newline() {
+        let delta = Delta::from(vec![Op::insert(
+            "Hello",
+            Some(attributes!("header" => 1, "italic" => true)),
+        )]);
+        let expected = Delta::from(vec![Op::insert(
+            "Hello",
+            Some(attributes!("italic" => true)),
+        )]);
+        assert_eq!(expected, delta.conform(&Schema::quill()));
+    }
+
+    #[test]
+    fn strips_inline_format_from_newline() {
+        let delta = Delta::from(vec![Op::insert(
+            "A\nB\n",
+            Some(attributes!("bold" => true)),
+        )]);
+        let expected = Delta::from(vec![
+            Op::insert("A", Some(attributes!("bold" => true))),
+            Op::insert("\n", None),
+            Op::insert("B", Some(attributes!("bold" => true))),
+            Op::insert("\n", None),
+        ]);
+        assert_eq!(expected, delta.conform(&Schema::quill()));
+    }
+
+    #[test]
+    fn strips_disallowed_values() {
+        let delta = Delta::from(vec![
+            Op::insert("Text", Some(attributes!("bold" => false))),
+            Op::insert("\n", Some(attributes!("header" => 9))),
+            Op::retain(2, Some(attributes!("script" => "middle", "color" => "red"))),
+        ]);
+        let expected = Delta::from(vec![
+            Op::insert("Text\n", None),
+            Op::retain(2, Some(attributes!("color" => "red"))),
+        ]);
+        assert_eq!(expected, delta.conform(&Schema::quill()));
+    }
+
+    #[test]
+    fn keeps_unregistered_keys() {
+        let delta = Delta::from(vec![
+            Op::insert("Hi\n", Some(attributes!("mention" => "bob"))),
+            Op::insert(json!({"poll": 3}), None),
+        ]);
+        assert_eq!(delta, delta.conform(&Schema::quill()));
+    }
+
+    #[test]
+    fn removes_misplaced_embeds() {
+        let delta = Delta::from(vec![
+            Op::insert(json!({"header": 1}), None),
+            Op::insert("Text", Some(attributes!("image" => "a.png"))),
+            Op::delete(2),
+        ]);
+        let expected = Delta::from(vec![Op::insert("Text", None), Op::delete(2)]);
+        assert_eq!(expected, delta.conform(&Schema::quill()));
+    }
+
+    #[test]
+    fn keeps_null_on_retain() {
+        let delta = Delta::from(vec![Op::retain(
+            3,
+            Some(attributes!("header" => Value::Null)),
+        )]);
+        assert_eq!(delta, delta.conform(&Schema::quill()));
+    }
+}
+
+#[cfg(test)]
+mod each_line_tests {
+    use serde_json::json;
+
+    use crate::{AttributesMap, Op};
+
+    use super::Delta;
+
+    fn lines(delta: &Delta) -> Vec<(Delta, AttributesMap, usize)> {
+        let mut lines = Vec::new();
+        delta.each_line(|line, attributes, index| {
+            lines.push((line.clone(), attributes.clone(), index));
+            true
+        });
+        lines
+    }
+
+    #[test]
+    fn expected() {
+        let delta = Delta::from(vec![
+            Op::insert("Hello\n\n", None),
+            Op::insert("World", Some(attributes!("bold" => true))),
+            Op::insert(json!({"image": "octocat.png"}), None),
+            Op::insert("\n", Some(attributes!("align" => "right"))),
+            Op::insert("!", None),
+        ]);
+        assert_eq!(
+            vec![
+                (
+                    Delta::from(vec![Op::insert("Hello", None)]),
+                    AttributesMap::new(),
+                    0
+                ),
+                (Delta::new(), AttributesMap::new(), 1),
+                (
+                    Delta::from(vec![
+                        Op::insert("World", Some(attributes!("bold" => true))),
+                        Op::insert(json!({"image": "octocat.png"}), None),
+                    ]),
+                    attributes!("align" => "right"),
+                    2
+                ),
+                (
+                    Delta::from(vec![Op::insert("!", None)]),
+                    AttributesMap::new(),
+                    3
+                ),
+            ],
+            lines(&delta)
+        );
+    }
+
+    #[test]
+    fn trailing_newline() {
+        let delta = Delta::from(vec![Op::insert("Hello\nWorld!\n", None)]);
+        assert_eq!(2, lines(&delta).len());
+    }
+
+    #[test]
+    fn non_document() {
+        let delta = Delta::new().retain(1, None).delete(2);
+        assert!(lines(&delta).is_empty());
+    }
+
+    #[test]
+    fn early_return() {
+        let delta = Delta::from(vec![Op::insert("Hello\nNew\nWorld!", None)]);
+        let mut count = 0;
+        delta.each_line(|_, _, index| {
+            count += 1;
+            index < 1
+        });
+        assert_eq!(2, count);
+    }
+}
+
+#[cfg(test)]
+mod get_format_tests {
+    use serde_json::json;
+
+    use crate::{AttributesMap, Op};
+
+    use super::Delta;
+
+    fn document() -> Delta {
+        Delta::from(vec![
+            Op::insert("Title", Some(attributes!("bold" => true))),
+            Op::insert("\n", Some(attributes!("header" => 1))),
+            Op::insert("One ", Some(attributes!("bold" => true, "color" => "red"))),
+            Op::insert("two", Some(attributes!("bold" => true))),
+            Op::insert("\n", Some(attributes!("list" => "bullet", "indent" => 1))),
+            Op::insert(json!({"image": "octocat.png"}), None),
+            Op::insert("\n", Some(attributes!("list" => "bullet"))),
+        ])
+    }
+
+    #[test]
+    fn within_line() {
+        let (inline, line) = document().get_format(6, 4);
+        assert_eq!(attributes!("bold" => true, "color" => "red"), inline);
+        assert_eq!(attributes!("list" => "bullet", "indent" => 1), line);
+    }
+
+    #[test]
+    fn across_lines() {
+        let (inline, line) = document().get_format(2, 10);
+        assert_eq!(attributes!("bold" => true), inline);
+        assert_eq!(AttributesMap::new(), line);
+
+        let (inline, line) = document().get_format(8, 7);
+        assert_eq!(AttributesMap::new(), inline);
+        assert_eq!(attributes!("list" => "bullet"), line);
+    }
+
+    #[test]
+    fn ignores_newlines() {
+        let (inline, line) = document().get_format(5, 1);
+        assert_eq!(AttributesMap::new(), inline);
+        assert_eq!(attributes!("header" => 1), line);
+
+        let (inline, _) = document().get_format(3, 3);
+        assert_eq!(attributes!("bold" => true), inline);
+    }
+
+    #[test]
+    fn collapsed() {
+        let (inline, line) = document().get_format(8, 0);
+        assert_eq!(attributes!("bold" => true, "color" => "red"), inline);
+        assert_eq!(attributes!("list" => "bullet", "indent" => 1), line);
+
+        let (inline, line) = document().get_format(6, 0);
+        assert_eq!(AttributesMap::new(), inline);
+        assert_eq!(attributes!("list" => "bullet", "indent" => 1), line);
+
+        let (inline, line) = document().get_format(5, 0);
+        assert_eq!(attributes!("bold" => true), inline);
+        assert_eq!(attributes!("header" => 1), line);
+    }
+}
+
+#[cfg(test)]
+mod format_builders_tests {
+    use serde_json::{Value, json};
+
+    use crate::{AttributesMap, Op};
+
+    use super::Delta;
+
+    fn document() -> Delta {
+        Delta::from(vec![
+            Op::insert("Title", Some(attributes!("bold" => true))),
+            Op::insert("\n", Some(attributes!("header" => 1))),
+            Op::insert("Some ", None),
+            Op::insert("text", Some(attributes!("italic" => true))),
+            Op::insert(json!({"image": "octocat.png"}), None),
+            Op::insert("\nEnd\n", None),
+        ])
+    }
+
+    #[test]
+    fn format_text() {
+        let doc = document();
+        let change = doc.format_text(3, 6, attributes!("color" => "red"));
+        assert_eq!(
+            Delta::from(vec![
+                Op::insert("Tit", Some(attributes!("bold" => true))),
+                Op::insert("le", Some(attributes!("bold" => true, "color" => "red"))),
+                Op::insert("\n", Some(attributes!("header" => 1))),
+                Op::insert("Som", Some(attributes!("color" => "red"))),
+                Op::insert("e ", None),
+                Op::insert("text", Some(attributes!("italic" => true))),
+                Op::insert(json!({"image": "octocat.png"}), None),
+                Op::insert("\nEnd\n", None),
+            ]),
+            doc.compose(&change)
+        );
+    }
+
+    #[test]
+    fn format_text_embed() {
+        let doc = document();
+        let change = doc.format_text(15, 1, attributes!("bold" => true));
+        assert_eq!(
+            Delta::new()
+                .retain(15, None)
+                .retain(1, Some(attributes!("bold" => true))),
+            change
+        );
+        let formatted = doc.compose(&change);
+        assert_eq!(
+            Delta::from(vec![
+                Op::insert("Title", Some(attributes!("bold" => true))),
+                Op::insert("\n", Some(attributes!("header" => 1))),
+                Op::insert("Some ", None),
+                Op::insert("text", Some(attributes!("italic" => true))),
+                Op::insert_any(
+                    json!({"image": "octocat.png"}),
+                    Some(attributes!("bold" => true))
+                ),
+                Op::insert("\nEnd\n", None),
+            ]),
+            formatted
+        );
+        assert_eq!(doc, formatted.compose(&formatted.remove_format(15, 1)));
+    }
+
+    #[test]
+    fn format_text_empty_range() {
+        assert_eq!(
+            Delta::new(),
+            document().format_text(3, 0, attributes!("bold" => true))
+        );
+    }
+
+    #[test]
+    fn format_line() {
+        let doc = document();
+        let change = doc.format_line(2, 5, attributes!("align" => "center"));
+        assert_eq!(
+            Delta::new()
+                .retain(5, None)
+                .retain(1, Some(attributes!("align" => "center")))
+                .retain(10, None)
+                .retain(1, Some(attributes!("align" => "center"))),
+            change
+        );
+        assert_eq!(
+            Delta::from(vec![
+                Op::insert("Title", Some(attributes!("bold" => true))),
+                Op::insert("\n", Some(attributes!("header" => 1, "align" => "center"))),
+                Op::insert("Some ", None),
+                Op::insert("text", Some(attributes!("italic" => true))),
+                Op::insert(json!({"image": "octocat.png"}), None),
+                Op::insert("\n", Some(attributes!("align" => "center"))),
+                Op::insert("End\n", None),
+            ]),
+            doc.compose(&change)
+        );
+    }
+
+    #[test]
+    fn format_line_collapsed() {
+        let change = document().format_line(19, 0, attributes!("list" => "bullet"));
+        assert_eq!(
+            Delta::new()
+                .retain(20, None)
+                .retain(1, Some(attributes!("list" => "bullet"))),
+            change
+        );
+    }
+
+    #[test]
+    fn format_line_without_trailing_newline() {
+        let doc = Delta::from(vec![Op::insert("One\nTwo", None)]);
+        let change = doc.format_line(0, 7, attributes!("list" => "bullet"));
+        assert_eq!(
+            Delta::new()
+                .retain(3, None)
+                .retain(1, Some(attributes!("list" => "bullet"))),
+            change
+        );
+    }
+
+    #[test]
+    fn remove_format() {
+        let doc = document();
+        let change = doc.remove_format(3, 8);
+        assert_eq!(
+            Delta::new()
+                .retain(3, None)
+                .retain(2, Some(attributes!("bold" => Value::Null)))
+                .retain(1, Some(attributes!("header" => Value::Null))),
+            change
+        );
+        assert_eq!(
+            Delta::from(vec![
+                Op::insert("Tit", Some(attributes!("bold" => true))),
+                Op::insert("le\nSome ", None),
+                Op::insert("text", Some(attributes!("italic" => true))),
+                Op::insert(json!({"image": "octocat.png"}), None),
+                Op::insert("\nEnd\n", None),
+            ]),
+            doc.compose(&change)
+        );
+    }
+
+    #[test]
+    fn remove_format_composes() {
+        let doc = document();
+        let plain = doc.compose(&doc.remove_format(0, doc.len()));
+        assert_eq!(
+            Delta::from(vec![
+                Op::insert("Title\nSome text", None),
+                Op::insert(json!({"image": "octocat.png"}), None),
+                Op::insert("\nEnd\n", None),
+            ]),
+            plain
+        );
+    }
+}
+
+#[cfg(test)]
+mod edit_builders_tests {
+    use serde_json::json;
+
+    use crate::{AttributesMap, Op};
+
+    use super::Delta;
+
+    fn document() -> Delta {
+        Delta::from(vec![
+            Op::insert("Title", Some(attributes!("bold" => true))),
+            Op::insert("\n", Some(attributes!("header" => 1))),
+            Op::insert("Some ", None),
+            Op::insert("text", Some(attributes!("italic" => true))),
+            Op::insert("\n", None),
+        ])
+    }
+
+    #[test]
+    fn insert_text_inherits() {
+        let doc = document();
+        let change = doc.insert_text(15, "ual", None);
+        assert_eq!(
+            Delta::new()
+                .retain(15, None)
+                .insert("ual", Some(attributes!("italic" => true))),
+            change
+        );
+    }
+
+    #[test]
+    fn insert_text_at_line_start() {
+        let doc = document();
+        let change = doc.insert_text(6, "Hey ", None);
+        assert_eq!(Delta::new().retain(6, None).insert("Hey ", None), change);
+        let change = doc.insert_text(0, "A ", None);
+        assert_eq!(Delta::new().insert("A ", None), change);
+    }
+
+    #[test]
+    fn insert_text_clamped() {
+        let doc = document();
+        let change = doc.insert_text(100, "!", None);
+        assert_eq!(Delta::new().retain(16, None).insert("!", None), change);
+        assert_eq!(17, doc.compose(&change).len());
+    }
+
+    #[test]
+    fn insert_text_with_attributes() {
+        let doc = document();
+        let change = doc.insert_text(2, "!", Some(attributes!("color" => "red")));
+        assert_eq!(
+            Delta::from(vec![
+                Op::insert("Ti", Some(attributes!("bold" => true))),
+                Op::insert("!", Some(attributes!("color" => "red"))),
+                Op::insert("tle", Some(attributes!("bold" => true))),
+                Op::insert("\n", Some(attributes!("header" => 1))),
+                Op::insert("Some ", None),
+                Op::insert("text", Some(attributes!("italic" => true))),
+                Op::insert("\n", None),
+            ]),
+            doc.compose(&change)
+        );
+    }
+
+    #[test]
+    fn insert_text_with_newlines() {
+        let doc = document();
+        let change = doc.insert_text(3, "\nNew\n", None);
+        assert_eq!(
+            Delta::from(vec![
+                Op::insert("Tit", Some(attributes!("bold" => true))),
+                Op::insert("\n", Some(attributes!("header" => 1))),
+                Op::insert("New", Some(attributes!("bold" => true))),
+                Op::insert("\n", Some(attributes!("header" => 1))),
+                Op::insert("le", Some(attributes!("bold" => true))),
+                Op::insert("\n", Some(attributes!("header" => 1))),
+                Op::insert("Some ", None),
+                Op::insert("text", Some(attributes!("italic" => true))),
+                Op::insert("\n", None),
+            ]),
+            doc.compose(&change)
+        );
+    }
+
+    #[test]
+    fn delete_text() {
+        let doc = document();
+        let change = doc.delete_text(3, 5);
+        assert_eq!(
+            Delta::from(vec![
+                Op::insert("Tit", Some(attributes!("bold" => true))),
+                Op::insert("me ", None),
+                Op::insert("text", Some(attributes!("italic" => true))),
+                Op::insert("\n", None),
+            ]),
+            doc.compose(&change)
+        );
+    }
+
+    #[test]
+    fn delete_text_clamped() {
+        let doc = document();
+        assert_eq!(
+            Delta::new().retain(10, None).delete(6),
+            doc.delete_text(10, 100)
+        );
+        assert_eq!(Delta::new(), doc.delete_text(100, 2));
+        assert_eq!(Delta::new(), doc.delete_text(3, 0));
+    }
+
+    #[test]
+    fn replace_range() {
+        let doc = document();
+        let change = doc.replace_range(11, 4, "words");
+        assert_eq!(
+            Delta::new()
+                .retain(11, None)
+                .insert("words", Some(attributes!("italic" => true)))
+                .delete(4),
+            change
+        );
+        assert_eq!(
+            Delta::from(vec![
+                Op::insert("Title", Some(attributes!("bold" => true))),
+                Op::insert("\n", Some(attributes!("header" => 1))),
+                Op::insert("Some ", None),
+                Op::insert("words", Some(attributes!("italic" => true))),
+                Op::insert("\n", None),
+            ]),
+            doc.compose(&change)
+        );
+    }
+
+    #[test]
+    fn replace_range_mixed_formats() {
+        let doc = document();
+        let change = doc.replace_range(8, 5, "abcdefg");
+        assert_eq!(
+            Delta::from(vec![
+                Op::insert("Title", Some(attributes!("bold" => true))),
+                Op::insert("\n", Some(attributes!("header" => 1))),
+                Op::insert("Soabc", None),
+                Op::insert("defgxt", Some(attributes!("italic" => true))),
+                Op::insert("\n", None),
+            ]),
+            doc.compose(&change)
+        );
+        let change = doc.replace_range(8, 5, "ab");
+        assert_eq!(
+            Delta::from(vec![
+                Op::insert("Title", Some(attributes!("bold" => true))),
+                Op::insert("\n", Some(attributes!("header" => 1))),
+                Op::insert("Soab", None),
+                Op::insert("xt", Some(attributes!("italic" => true))),
+                Op::insert("\n", None),
+            ]),
+            doc.compose(&change)
+        );
+    }
+
+    #[test]
+    fn replace_range_joining_lines() {
+        let doc = document();
+        let change = doc.replace_range(3, 5, "ABCD");
+        assert_eq!(
+            Delta::from(vec![
+                Op::insert("TitAB", Some(attributes!("bold" => true))),
+                Op::insert("CDme ", None),
+                Op::insert("text", Some(attributes!("italic" => true))),
+                Op::insert("\n", Some(attributes!("header" => 1))),
+            ]),
+            doc.compose(&change)
+        );
+    }
+
+    #[test]
+    fn replace_range_clamped() {
+        let doc = document();
+        assert_eq!(
+            doc.insert_text(16, "!", None),
+            doc.replace_range(20, 3, "!")
+        );
+        assert_eq!(
+            Delta::new()
+                .retain(15, None)
+                .insert("!", Some(attributes!("italic" => true)))
+                .delete(1),
+            doc.replace_range(15, 10, "!")
+        );
+    }
+
+    #[test]
+    fn replace_range_empty() {
+        let doc = document();
+        assert_eq!(doc.insert_text(2, "x", None), doc.replace_range(2, 0, "x"));
+    }
+
+    #[test]
+    fn replace_range_with_embed() {
+        let doc = Delta::from(vec![
+            Op::insert(json!({"image": "octocat.png"}), None),
+            Op::insert("\n", None),
+        ]);
+        let change = doc.replace_range(0, 1, "[image]");
+        assert_eq!(
+            Delta::from(vec![Op::insert("[image]\n", None)]),
+            doc.compose(&change)
+        );
+    }
+}
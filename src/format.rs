@@ -0,0 +1,365 @@
+use serde_json::Value;
+
+/// Kind of list a line belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ListKind {
+    Ordered,
+    Bullet,
+    Checked,
+    Unchecked,
+}
+
+impl ListKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ListKind::Ordered => "ordered",
+            ListKind::Bullet => "bullet",
+            ListKind::Checked => "checked",
+            ListKind::Unchecked => "unchecked",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "ordered" => Some(ListKind::Ordered),
+            "bullet" => Some(ListKind::Bullet),
+            "checked" => Some(ListKind::Checked),
+            "unchecked" => Some(ListKind::Unchecked),
+            _ => None,
+        }
+    }
+}
+
+/// Horizontal alignment of a line
+///
+/// Left alignment is Quill's default and is expressed by the absence of the `align` format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Align {
+    Center,
+    Right,
+    Justify,
+}
+
+impl Align {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Align::Center => "center",
+            Align::Right => "right",
+            Align::Justify => "justify",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "center" => Some(Align::Center),
+            "right" => Some(Align::Right),
+            "justify" => Some(Align::Justify),
+            _ => None,
+        }
+    }
+}
+
+/// Vertical position of inline text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Script {
+    Sub,
+    Super,
+}
+
+impl Script {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Script::Sub => "sub",
+            Script::Super => "super",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "sub" => Some(Script::Sub),
+            "super" => Some(Script::Super),
+            _ => None,
+        }
+    }
+}
+
+/// Typed view of a Quill format
+///
+/// Every `(key, value)` attribute pair converts to a [Format] and back without loss:
+/// pairs that don't match the canonical shape of a known format (e.g. `"bold": null` or
+/// `"header": "1"`) are kept as [Format::Other].
+///
+/// # Example
+///
+/// ```
+/// use quill_delta_rs::Format;
+/// use serde_json::Value;
+///
+/// let format = Format::from_entry("header", &Value::from(2));
+/// assert_eq!(Format::Header(2), format);
+/// assert_eq!(("header".to_string(), Value::from(2)), format.into_entry());
+///
+/// let unknown = Format::from_entry("mention", &Value::from("@bob"));
+/// assert_eq!(Format::Other("mention".to_string(), Value::from("@bob")), unknown);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Format {
+    // Inline formats
+    Bold,
+    Italic,
+    Underline,
+    Strike,
+    Code,
+    Script(Script),
+    Link(String),
+    Color(String),
+    Background(String),
+    Font(String),
+    Size(String),
+    // Line formats
+    Header(u8),
+    List(ListKind),
+    Indent(u8),
+    Align(Align),
+    Blockquote,
+    /// Code block, with its language if any
+    CodeBlock(Option<String>),
+    /// Right-to-left text direction
+    Rtl,
+    /// Any attribute that is not a known format
+    Other(String, Value),
+}
+
+impl Format {
+    /// Build a [Format] from an attribute key and value
+    pub fn from_entry(key: &str, value: &Value) -> Self {
+        let format = match (key, value) {
+            ("bold", Value::Bool(true)) => Some(Format::Bold),
+            ("italic", Value::Bool(true)) => Some(Format::Italic),
+            ("underline", Value::Bool(true)) => Some(Format::Underline),
+            ("strike", Value::Bool(true)) => Some(Format::Strike),
+            ("code", Value::Bool(true)) => Some(Format::Code),
+            ("script", Value::String(s)) => Script::parse(s).map(Format::Script),
+            ("link", Value::String(s)) => Some(Format::Link(s.clone())),
+            ("color", Value::String(s)) => Some(Format::Color(s.clone())),
+            ("background", Value::String(s)) => Some(Format::Background(s.clone())),
+            ("font", Value::String(s)) => Some(Format::Font(s.clone())),
+            ("size", Value::String(s)) => Some(Format::Size(s.clone())),
+            ("header", Value::Number(n)) => n
+                .as_u64()
+                .and_then(|n| u8::try_from(n).ok())
+                .map(Format::Header),
+            ("list", Value::String(s)) => ListKind::parse(s).map(Format::List),
+            ("indent", Value::Number(n)) => n
+                .as_u64()
+                .and_then(|n| u8::try_from(n).ok())
+                .map(Format::Indent),
+            ("align", Value::String(s)) => Align::parse(s).map(Format::Align),
+            ("blockquote", Value::Bool(true)) => Some(Format::Blockquote),
+            ("code-block", Value::Bool(true)) => Some(Format::CodeBlock(None)),
+            ("code-block", Value::String(s)) => Some(Format::CodeBlock(Some(s.clone()))),
+            ("direction", Value::String(s)) if s == "rtl" => Some(Format::Rtl),
+            _ => None,
+        };
+        format.unwrap_or_else(|| Format::Other(key.to_string(), value.clone()))
+    }
+
+    /// The attribute key of the format
+    pub fn key(&self) -> &str {
+        match self {
+            Format::Bold => "bold",
+            Format::Italic => "italic",
+            Format::Underline => "underline",
+            Format::Strike => "strike",
+            Format::Code => "code",
+            Format::Script(_) => "script",
+            Format::Link(_) => "link",
+            Format::Color(_) => "color",
+            Format::Background(_) => "background",
+            Format::Font(_) => "font",
+            Format::Size(_) => "size",
+            Format::Header(_) => "header",
+            Format::List(_) => "list",
+            Format::Indent(_) => "indent",
+            Format::Align(_) => "align",
+            Format::Blockquote => "blockquote",
+            Format::CodeBlock(_) => "code-block",
+            Format::Rtl => "direction",
+            Format::Other(key, _) => key,
+        }
+    }
+
+    /// The attribute value of the format
+    pub fn value(&self) -> Value {
+        match self {
+            Format::Bold
+            | Format::Italic
+            | Format::Underline
+            | Format::Strike
+            | Format::Code
+            | Format::Blockquote
+            | Format::CodeBlock(None) => Value::Bool(true),
+            Format::Script(script) => Value::from(script.as_str()),
+            Format::Link(s)
+            | Format::Color(s)
+            | Format::Background(s)
+            | Format::Font(s)
+            | Format::Size(s)
+            | Format::CodeBlock(Some(s)) => Value::from(s.as_str()),
+            Format::Header(level) => Value::from(*level),
+            Format::List(kind) => Value::from(kind.as_str()),
+            Format::Indent(level) => Value::from(*level),
+            Format::Align(align) => Value::from(align.as_str()),
+            Format::Rtl => Value::from("rtl"),
+            Format::Other(_, value) => value.clone(),
+        }
+    }
+
+    /// Convert the format into an attribute key and value
+    pub fn into_entry(self) -> (String, Value) {
+        match self {
+            Format::Other(key, value) => (key, value),
+            format => (format.key().to_string(), format.value()),
+        }
+    }
+}
+
+impl From<(String, Value)> for Format {
+    fn from((key, value): (String, Value)) -> Self {
+        Format::from_entry(&key, &value)
+    }
+}
+
+impl From<Format> for (String, Value) {
+    fn from(format: Format) -> Self {
+        format.into_entry()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Value, json};
+
+    use crate::AttributesMap;
+
+    use super::{Align, Format, ListKind, Script};
+
+    fn round_trip(key: &str, value: Value) -> Format {
+        let format = Format::from_entry(key, &value);
+        assert_eq!((key.to_string(), value), format.clone().into_entry());
+        format
+    }
+
+    #[test]
+    fn inline_formats() {
+        assert_eq!(Format::Bold, round_trip("bold", json!(true)));
+        assert_eq!(Format::Italic, round_trip("italic", json!(true)));
+        assert_eq!(Format::Underline, round_trip("underline", json!(true)));
+        assert_eq!(Format::Strike, round_trip("strike", json!(true)));
+        assert_eq!(Format::Code, round_trip("code", json!(true)));
+        assert_eq!(
+            Format::Script(Script::Super),
+            round_trip("script", json!("super"))
+        );
+        assert_eq!(
+            Format::Link("https://quilljs.com".to_string()),
+            round_trip("link", json!("https://quilljs.com"))
+        );
+        assert_eq!(
+            Format::Color("#ff0000".to_string()),
+            round_trip("color", json!("#ff0000"))
+        );
+        assert_eq!(
+            Format::Size("large".to_string()),
+            round_trip("size", json!("large"))
+        );
+    }
+
+    #[test]
+    fn line_formats() {
+        assert_eq!(Format::Header(1), round_trip("header", json!(1)));
+        assert_eq!(
+            Format::List(ListKind::Bullet),
+            round_trip("list", json!("bullet"))
+        );
+        assert_eq!(Format::Indent(3), round_trip("indent", json!(3)));
+        assert_eq!(
+            Format::Align(Align::Center),
+            round_trip("align", json!("center"))
+        );
+        assert_eq!(Format::Blockquote, round_trip("blockquote", json!(true)));
+        assert_eq!(
+            Format::CodeBlock(None),
+            round_trip("code-block", json!(true))
+        );
+        assert_eq!(
+            Format::CodeBlock(Some("rust".to_string())),
+            round_trip("code-block", json!("rust"))
+        );
+        assert_eq!(Format::Rtl, round_trip("direction", json!("rtl")));
+    }
+
+    #[test]
+    fn non_canonical_values_are_other() {
+        assert_eq!(
+            Format::Other("bold".to_string(), Value::Null),
+            round_trip("bold", Value::Null)
+        );
+        assert_eq!(
+            Format::Other("bold".to_string(), json!(false)),
+            round_trip("bold", json!(false))
+        );
+        assert_eq!(
+            Format::Other("header".to_string(), json!("1")),
+            round_trip("header", json!("1"))
+        );
+        assert_eq!(
+            Format::Other("header".to_string(), json!(1024)),
+            round_trip("header", json!(1024))
+        );
+        assert_eq!(
+            Format::Other("list".to_string(), json!("roman")),
+            round_trip("list", json!("roman"))
+        );
+        assert_eq!(
+            Format::Other("alt".to_string(), json!("Quill")),
+            round_trip("alt", json!("Quill"))
+        );
+    }
+
+    #[test]
+    fn attributes_round_trip() {
+        let attributes = attributes!(
+            "bold" => true,
+            "header" => 2,
+            "color" => Value::Null,
+            "mention" => json!({"id": 4}),
+        );
+        let formats = attributes.formats();
+        assert_eq!(4, formats.len());
+        assert!(formats.contains(&Format::Bold));
+        assert!(formats.contains(&Format::Header(2)));
+        assert_eq!(attributes, formats.into_iter().collect::<AttributesMap>());
+    }
+
+    #[test]
+    fn get_format() {
+        let attributes = attributes!("list" => "ordered");
+        assert_eq!(
+            Some(Format::List(ListKind::Ordered)),
+            attributes.get_format("list")
+        );
+        assert_eq!(None, attributes.get_format("header"));
+    }
+
+    #[test]
+    fn insert_format() {
+        let mut attributes = AttributesMap::new();
+        attributes.insert_format(Format::Header(3));
+        assert_eq!(
+            Some(Value::from(3)),
+            attributes.insert_format(Format::Header(1))
+        );
+        assert_eq!(attributes!("header" => 1), attributes);
+    }
+}
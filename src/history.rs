@@ -0,0 +1,351 @@
+use std::time::{Duration, Instant};
+
+use crate::delta::Delta;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct StackItem {
+    /// Change redoing the item
+    change: Delta,
+    /// Inverse of the change, undoing the item
+    inverse: Delta,
+}
+
+/// Undo and redo stacks of the local changes made to a document
+///
+/// Modeled on Quill's History module: changes recorded within `delay` of the first change of
+/// the last undo item are merged into it, and the undo stack keeps at most `max_stack`
+/// items. Remote changes are passed to [History::transform] so that undo and redo only
+/// revert and reapply the local user's edits.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use quill_delta_rs::{Delta, History};
+///
+/// let mut history = History::new().delay(Duration::ZERO);
+/// let mut doc = Delta::new().insert("Hello\n", None);
+///
+/// let change = Delta::new().retain(5, None).insert(" World", None);
+/// history.record(&change, &doc);
+/// doc = doc.compose(&change);
+///
+/// // A collaborator prepends some text
+/// let remote = Delta::new().insert("Oh ", None);
+/// history.transform(&remote);
+/// doc = doc.compose(&remote);
+///
+/// doc = doc.compose(&history.undo().unwrap());
+/// assert_eq!(Delta::new().insert("Oh Hello\n", None), doc);
+/// doc = doc.compose(&history.redo().unwrap());
+/// assert_eq!(Delta::new().insert("Oh Hello World\n", None), doc);
+/// ```
+#[derive(Debug, Clone)]
+pub struct History {
+    delay: Duration,
+    max_stack: usize,
+    undo: Vec<StackItem>,
+    redo: Vec<StackItem>,
+    last_recorded: Option<Instant>,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        History {
+            delay: Duration::from_millis(1000),
+            max_stack: 100,
+            undo: Vec::new(),
+            redo: Vec::new(),
+            last_recorded: None,
+        }
+    }
+}
+
+impl History {
+    /// Create an empty [History] with Quill's defaults: 1 second delay and 100 items.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the delay within which recorded changes are merged into a single undo item
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Set the maximum number of undo items, dropping the oldest ones beyond it
+    pub fn max_stack(mut self, max_stack: usize) -> Self {
+        self.max_stack = max_stack;
+        self
+    }
+
+    /// Record a local `change` made to the document `base`
+    pub fn record(&mut self, change: &Delta, base: &Delta) {
+        self.record_at(change, base, Instant::now());
+    }
+
+    /// Record a local `change` made to the document `base` at a given time
+    ///
+    /// Recording clears the redo stack.
+    pub fn record_at(&mut self, change: &Delta, base: &Delta, at: Instant) {
+        if change.ops().is_empty() {
+            return;
+        }
+        self.redo.clear();
+        let mut change = change.clone();
+        let mut inverse = change.invert(base);
+        match self.last_recorded {
+            Some(last)
+                if at.saturating_duration_since(last) < self.delay && !self.undo.is_empty() =>
+            {
+                let item = self.undo.pop().unwrap();
+                inverse = inverse.compose(&item.inverse);
+                change = item.change.compose(&change);
+            }
+            _ => self.last_recorded = Some(at),
+        }
+        if inverse.is_empty() {
+            return;
+        }
+        self.undo.push(StackItem { change, inverse });
+        if self.undo.len() > self.max_stack {
+            self.undo.remove(0);
+        }
+    }
+
+    /// Stop merging: the next recorded change starts a new undo item
+    pub fn cutoff(&mut self) {
+        self.last_recorded = None;
+    }
+
+    /// Undo the last item, returning the change to apply to the document
+    pub fn undo(&mut self) -> Option<Delta> {
+        let item = self.undo.pop()?;
+        let inverse = item.inverse.clone();
+        self.redo.push(item);
+        self.last_recorded = None;
+        Some(inverse)
+    }
+
+    /// Redo the last undone item, returning the change to apply to the document
+    pub fn redo(&mut self) -> Option<Delta> {
+        let item = self.redo.pop()?;
+        let change = item.change.clone();
+        self.undo.push(item);
+        self.last_recorded = None;
+        Some(change)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.last_recorded = None;
+    }
+
+    /// Transform both stacks against a remote change applied to the document
+    ///
+    /// Items left without effect, e.g. because the remote change deleted the text they
+    /// inserted, are dropped.
+    pub fn transform(&mut self, remote: &Delta) {
+        // The top undo item applies to the current document, each older one to the document
+        // its successor's inverse results in
+        let mut remote_delta = remote.clone();
+        for i in (0..self.undo.len()).rev() {
+            let item = &self.undo[i];
+            let inverse = remote_delta.transform(&item.inverse, true);
+            remote_delta = item.inverse.transform(&remote_delta, false);
+            let change = remote_delta.transform(&item.change, true);
+            if inverse.is_empty() {
+                self.undo.remove(i);
+            } else {
+                self.undo[i] = StackItem { change, inverse };
+            }
+        }
+        // The top redo item applies to the current document, each older one to the document
+        // its successor's change results in
+        let mut remote_delta = remote.clone();
+        for i in (0..self.redo.len()).rev() {
+            let item = &self.redo[i];
+            let change = remote_delta.transform(&item.change, true);
+            remote_delta = item.change.transform(&remote_delta, false);
+            let inverse = remote_delta.transform(&item.inverse, true);
+            if change.is_empty() {
+                self.redo.remove(i);
+            } else {
+                self.redo[i] = StackItem { change, inverse };
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::{AttributesMap, Delta};
+
+    use super::History;
+
+    fn doc() -> Delta {
+        Delta::new().insert("The lazy fox\n", None)
+    }
+
+    #[test]
+    fn undo_redo() {
+        let mut history = History::new();
+        let doc = doc();
+        let change = Delta::new().retain(4, None).delete(5);
+        history.record(&change, &doc);
+        let changed = doc.compose(&change);
+
+        let undo = history.undo().unwrap();
+        assert_eq!(doc, changed.compose(&undo));
+        assert!(!history.can_undo());
+        let redo = history.redo().unwrap();
+        assert_eq!(changed, doc.compose(&redo));
+        assert!(!history.can_redo());
+        assert!(history.can_undo());
+    }
+
+    #[test]
+    fn empty() {
+        let mut history = History::new();
+        assert_eq!(None, history.undo());
+        assert_eq!(None, history.redo());
+        history.record(&Delta::new(), &doc());
+        assert!(!history.can_undo());
+    }
+
+    #[test]
+    fn merge_within_delay() {
+        let mut history = History::new().delay(Duration::from_millis(500));
+        let start = Instant::now();
+        let mut doc = doc();
+        let original = doc.clone();
+        for (i, text) in ["a", "b"].into_iter().enumerate() {
+            let change = Delta::new().retain(12 + i, None).insert(text, None);
+            history.record_at(&change, &doc, start + Duration::from_millis(100 * i as u64));
+            doc = doc.compose(&change);
+        }
+        let change = Delta::new().retain(14, None).insert("c", None);
+        history.record_at(&change, &doc, start + Duration::from_millis(600));
+        doc = doc.compose(&change);
+
+        doc = doc.compose(&history.undo().unwrap());
+        assert_eq!(Delta::new().insert("The lazy foxab\n", None), doc);
+        doc = doc.compose(&history.undo().unwrap());
+        assert_eq!(original, doc);
+        assert!(!history.can_undo());
+    }
+
+    #[test]
+    fn cutoff() {
+        let mut history = History::new();
+        let mut doc = doc();
+        let at = Instant::now();
+        let change = Delta::new().insert("A", None);
+        history.record_at(&change, &doc, at);
+        doc = doc.compose(&change);
+        history.cutoff();
+        history.record_at(&Delta::new().insert("B", None), &doc, at);
+        history.undo();
+        assert!(history.can_undo());
+    }
+
+    #[test]
+    fn record_clears_redo() {
+        let mut history = History::new().delay(Duration::ZERO);
+        let doc = doc();
+        history.record(&Delta::new().insert("A", None), &doc);
+        history.undo();
+        assert!(history.can_redo());
+        history.record(&Delta::new().insert("B", None), &doc);
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn max_stack() {
+        let mut history = History::new().delay(Duration::ZERO).max_stack(2);
+        let mut doc = doc();
+        for text in ["a", "b", "c"] {
+            let change = Delta::new().insert(text, None);
+            history.record(&change, &doc);
+            doc = doc.compose(&change);
+        }
+        doc = doc.compose(&history.undo().unwrap());
+        doc = doc.compose(&history.undo().unwrap());
+        assert!(history.undo().is_none());
+        assert_eq!(Delta::new().insert("aThe lazy fox\n", None), doc);
+    }
+
+    #[test]
+    fn transform_undo_stack() {
+        let mut history = History::new().delay(Duration::ZERO);
+        let mut doc = doc();
+        for change in [
+            Delta::new().retain(4, None).insert("quick ", None),
+            Delta::new()
+                .retain(15, None)
+                .retain(3, Some(attributes!("bold" => true))),
+        ] {
+            history.record(&change, &doc);
+            doc = doc.compose(&change);
+        }
+        let remote = Delta::new()
+            .insert("See: ", None)
+            .retain(18, None)
+            .insert(" jumps", None);
+        history.transform(&remote);
+        doc = doc.compose(&remote);
+
+        doc = doc.compose(&history.undo().unwrap());
+        doc = doc.compose(&history.undo().unwrap());
+        assert_eq!(Delta::new().insert("See: The lazy fox jumps\n", None), doc);
+
+        doc = doc.compose(&history.redo().unwrap());
+        doc = doc.compose(&history.redo().unwrap());
+        assert_eq!(
+            Delta::new()
+                .insert("See: The quick lazy ", None)
+                .insert("fox", Some(attributes!("bold" => true)))
+                .insert(" jumps\n", None),
+            doc
+        );
+    }
+
+    #[test]
+    fn transform_redo_stack() {
+        let mut history = History::new().delay(Duration::ZERO);
+        let mut doc = doc();
+        let change = Delta::new().retain(9, None).insert("red ", None);
+        history.record(&change, &doc);
+        doc = doc.compose(&change);
+        doc = doc.compose(&history.undo().unwrap());
+
+        let remote = Delta::new().insert("Oh, ", None);
+        history.transform(&remote);
+        doc = doc.compose(&remote);
+        doc = doc.compose(&history.redo().unwrap());
+        assert_eq!(Delta::new().insert("Oh, The lazy red fox\n", None), doc);
+    }
+
+    #[test]
+    fn transform_drops_deleted_items() {
+        let mut history = History::new();
+        let mut doc = doc();
+        let change = Delta::new().retain(4, None).insert("quick ", None);
+        history.record(&change, &doc);
+        doc = doc.compose(&change);
+
+        history.transform(&Delta::new().delete(doc.len() - 1));
+        assert!(!history.can_undo());
+    }
+}
@@ -91,7 +91,7 @@ impl Iterator {
                 .collect();
             Op::insert(Value::from(sub_string), next_op.attributes().cloned())
         } else {
-            Op::insert(next_op.value(), next_op.attributes().cloned())
+            Op::insert_any(next_op.value(), next_op.attributes().cloned())
         }
     }
 
@@ -116,7 +116,7 @@ impl Iterator {
     /// Get the [OpType] of the next [Op] without affecting the iterator.
     ///
     /// Returns ```OpType::RETAIN(usize::MAX)``` if no more [Op] available.
-    pub fn peek_type<'a>(&'a self) -> &'a OpType {
+    pub fn peek_type(&self) -> &OpType {
         if self.index >= self.ops.len() {
             &OpType::Retain(usize::MAX)
         } else {
@@ -196,8 +196,8 @@ mod tests {
     use serde_json::json;
 
     use crate::{
+        AttributesMap,
         op::{Op, OpType},
-        {AttributesMap, attributes},
     };
 
     use super::Iterator;
@@ -269,12 +269,12 @@ mod tests {
     fn has_next() {
         let ops = vec![Op::insert("Hello", Some(attributes!("bold" => true)))];
         let mut iter = Iterator::from(ops);
-        assert_eq!(true, iter.has_next());
+        assert!(iter.has_next());
         assert_eq!(
             Op::insert("Hello", Some(attributes!("bold" => true)),),
             iter.next().unwrap()
         );
-        assert_eq!(false, iter.has_next());
+        assert!(!iter.has_next());
     }
 
     #[test]
@@ -0,0 +1,190 @@
+use std::fmt;
+
+use serde::{
+    Deserialize, Deserializer, Serialize, Serializer,
+    de::{self, IgnoredAny, MapAccess, SeqAccess, Visitor},
+    ser::SerializeStruct,
+};
+
+use crate::{delta::Delta, op::Op};
+
+/// JSON form of a [Delta]
+///
+/// [Delta] serializes as [JsonFormat::Delta] by default; use [Delta::json] to serialize it as
+/// quill-delta and Quill's `setContents` expect it, with [JsonFormat::Ops]. All forms are
+/// accepted when deserializing.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum JsonFormat {
+    /// `{"delta": [...]}`
+    #[default]
+    Delta,
+    /// `{"ops": [...]}`, as quill-delta
+    Ops,
+    /// `[...]`, the bare array of ops
+    Bare,
+}
+
+impl JsonFormat {
+    /// Key of the ops, [None] for the bare array
+    pub(crate) fn key(&self) -> Option<&'static str> {
+        match self {
+            JsonFormat::Delta => Some("delta"),
+            JsonFormat::Ops => Some("ops"),
+            JsonFormat::Bare => None,
+        }
+    }
+}
+
+/// A [Delta] serialized in a given [JsonFormat], see [Delta::json]
+#[derive(Debug, Clone, Copy)]
+pub struct DeltaJson<'a> {
+    delta: &'a Delta,
+    format: JsonFormat,
+}
+
+impl Serialize for DeltaJson<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.format.key() {
+            Some(key) => {
+                let mut state = serializer.serialize_struct("Delta", 1)?;
+                state.serialize_field(key, self.delta.ops())?;
+                state.end()
+            }
+            None => serializer.collect_seq(self.delta.ops()),
+        }
+    }
+}
+
+impl Delta {
+    /// Serialize this [Delta] in the given [JsonFormat]
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use quill_delta_rs::{Delta, JsonFormat};
+    /// use serde_json::json;
+    ///
+    /// let delta = Delta::new().insert("Hello", None);
+    /// assert_eq!(
+    ///     json!({"ops": [{"insert": "Hello"}]}),
+    ///     serde_json::to_value(delta.json(JsonFormat::Ops)).unwrap()
+    /// );
+    /// assert_eq!(
+    ///     json!([{"insert": "Hello"}]),
+    ///     serde_json::to_value(delta.json(JsonFormat::Bare)).unwrap()
+    /// );
+    /// assert_eq!(delta, serde_json::from_value(json!([{"insert": "Hello"}])).unwrap());
+    /// ```
+    pub fn json(&self, format: JsonFormat) -> DeltaJson<'_> {
+        DeltaJson {
+            delta: self,
+            format,
+        }
+    }
+}
+
+struct DeltaVisitor;
+
+impl<'de> Visitor<'de> for DeltaVisitor {
+    type Value = Delta;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a delta or an array of ops")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Delta, A::Error> {
+        let mut ops = Vec::new();
+        while let Some(op) = seq.next_element::<Op>()? {
+            ops.push(op);
+        }
+        Ok(Delta::from(ops))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Delta, A::Error> {
+        let mut ops: Option<Vec<Op>> = None;
+        while let Some(key) = map.next_key::<String>()? {
+            if key != "delta" && key != "ops" {
+                map.next_value::<IgnoredAny>()?;
+                continue;
+            }
+            if ops.is_some() {
+                return Err(de::Error::duplicate_field("delta"));
+            }
+            ops = Some(map.next_value()?);
+        }
+        let ops = ops.ok_or_else(|| de::Error::missing_field("delta"))?;
+        Ok(Delta::from(ops))
+    }
+}
+
+// Any JsonFormat is accepted, which requires a self-describing format
+impl<'de> Deserialize<'de> for Delta {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(DeltaVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{AttributesMap, Delta};
+
+    use super::JsonFormat;
+
+    fn delta() -> Delta {
+        Delta::new()
+            .insert("Test", None)
+            .retain(4, Some(attributes!("bold" => true)))
+            .delete(2)
+    }
+
+    fn ops() -> serde_json::Value {
+        json!([
+            {"insert": "Test"},
+            {"retain": 4, "attributes": {"bold": true}},
+            {"delete": 2}
+        ])
+    }
+
+    #[test]
+    fn serialize() {
+        let value = |format| serde_json::to_value(delta().json(format)).unwrap();
+        assert_eq!(json!({"delta": ops()}), value(JsonFormat::Delta));
+        assert_eq!(json!({"ops": ops()}), value(JsonFormat::Ops));
+        assert_eq!(ops(), value(JsonFormat::Bare));
+        assert_eq!(
+            serde_json::to_value(delta()).unwrap(),
+            value(JsonFormat::default())
+        );
+    }
+
+    #[test]
+    fn deserialize() {
+        for value in [
+            json!({"delta": ops()}),
+            json!({"ops": ops()}),
+            json!({"version": 1, "ops": ops()}),
+            ops(),
+        ] {
+            assert_eq!(delta(), serde_json::from_value(value).unwrap());
+        }
+        let delta: Delta = serde_json::from_str(r#"[{"insert": "Test"}]"#).unwrap();
+        assert_eq!(Delta::new().insert("Test", None), delta);
+    }
+
+    #[test]
+    fn deserialize_errors() {
+        let error = |value| {
+            serde_json::from_value::<Delta>(value)
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!("missing field `delta`", error(json!({"version": 1})));
+        assert_eq!(
+            "duplicate field `delta`",
+            error(json!({"delta": [], "ops": []}))
+        );
+        assert!(error(json!("Test")).contains("a delta or an array of ops"));
+    }
+}
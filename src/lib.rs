@@ -1,10 +1,66 @@
 #[macro_use]
 mod attributes;
+mod anchor;
+mod annotation;
+mod attribution;
+mod binary;
+mod client;
 mod delta;
+mod format;
+mod history;
 mod iter;
+mod json;
+mod merge;
 mod op;
+#[cfg(feature = "python")]
+mod python;
+mod range;
+mod redline;
+#[cfg(feature = "html")]
+mod renderer;
+mod sanitizer;
+mod schema;
+mod search;
+mod server;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+mod squash;
+mod storage;
+mod stream;
+mod suggestion;
+mod timeline;
+#[cfg(feature = "wasm")]
+mod wasm;
 
+pub use crate::anchor::{Anchor, AnchorSet, Gravity};
+pub use crate::annotation::{Annotation, Annotations};
 pub use crate::attributes::AttributesMap;
+pub use crate::attribution::{Attribution, Authorship};
+pub use crate::binary::{DecodeError, Decoder, Encoder};
+pub use crate::client::{Client, ClientError, ClientState};
 pub use crate::delta::Delta;
+pub use crate::format::{Align, Format, ListKind, Script};
+pub use crate::history::History;
 pub use crate::iter::Iterator;
+pub use crate::json::{DeltaJson, JsonFormat};
+pub use crate::merge::{Conflict, merge};
 pub use crate::op::{Op, OpType};
+#[cfg(feature = "python")]
+pub use crate::python::{PyAttributesMap, PyDelta, PyOp};
+pub use crate::range::Range;
+pub use crate::redline::{ADDED, CHANGED, REMOVED};
+#[cfg(feature = "html")]
+pub use crate::renderer::DeltaHtml;
+pub use crate::sanitizer::{Rule, Sanitizer, Stripped};
+pub use crate::schema::{Schema, Scope};
+pub use crate::search::Query;
+pub use crate::server::{Server, ServerError};
+#[cfg(feature = "sqlite")]
+pub use crate::sqlite::SqliteStorage;
+pub use crate::squash::{Change, SquashError, squash, verify};
+pub use crate::storage::{MemoryStorage, Storage, StorageError};
+pub use crate::stream::{OpReader, OpWriter};
+pub use crate::suggestion::{SUGGESTION_DELETE, SUGGESTION_FORMAT, SUGGESTION_INSERT};
+pub use crate::timeline::Timeline;
+#[cfg(feature = "wasm")]
+pub use crate::wasm::WasmDelta;
@@ -0,0 +1,267 @@
+use crate::{delta::Delta, op::Op, range::Range};
+
+/// Edits of both sides of a [merge] touching the same part of the base document
+///
+/// `base` covers both edits in the base document, `ours` and `theirs` the text each edit
+/// results in, in the respective document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Conflict {
+    pub base: Range,
+    pub ours: Range,
+    pub theirs: Range,
+}
+
+/// A run of consecutive non plain retain [Op](crate::Op)s of a change
+#[derive(Debug, Clone, Copy)]
+struct Hunk {
+    /// Range replaced or formatted in the base document
+    base: Range,
+    /// Range of the result in the changed document
+    changed: Range,
+    /// Indexes of the first op of the hunk and of the op after it in the change
+    ops: (usize, usize),
+}
+
+impl Hunk {
+    fn overlaps(&self, other: &Hunk) -> bool {
+        let (a, b) = (self.base, other.base);
+        match (a.is_collapsed(), b.is_collapsed()) {
+            (true, true) => a.index == b.index,
+            (true, false) => b.index < a.index && a.index < b.end(),
+            (false, true) => a.index < b.index && b.index < a.end(),
+            (false, false) => a.index < b.end() && b.index < a.end(),
+        }
+    }
+}
+
+fn hunks(change: &Delta) -> Vec<Hunk> {
+    let mut hunks: Vec<Hunk> = Vec::new();
+    let mut base = 0;
+    let mut changed = 0;
+    let mut in_hunk = false;
+    for (index, op) in change.ops().iter().enumerate() {
+        let length = op.len();
+        if op.is_retain() && op.attributes().is_none() {
+            base += length;
+            changed += length;
+            in_hunk = false;
+            continue;
+        }
+        if !in_hunk {
+            hunks.push(Hunk {
+                base: Range::new(base, 0),
+                changed: Range::new(changed, 0),
+                ops: (index, index),
+            });
+            in_hunk = true;
+        }
+        let hunk = hunks.last_mut().unwrap();
+        hunk.ops.1 = index + 1;
+        if !op.is_insert() {
+            base += length;
+            hunk.base.length += length;
+        }
+        if !op.is_delete() {
+            changed += length;
+            hunk.changed.length += length;
+        }
+    }
+    hunks
+}
+
+/// `change` without the edits of the given hunks, their base text being retained as is
+fn discard(change: &Delta, hunks: &[Hunk]) -> Delta {
+    let mut discarded = vec![false; change.ops().len()];
+    for hunk in hunks {
+        discarded[hunk.ops.0..hunk.ops.1].fill(true);
+    }
+    let mut kept = Delta::new();
+    for (op, discarded) in change.ops().iter().zip(discarded) {
+        if !discarded {
+            kept.push(op.clone());
+        } else if !op.is_insert() {
+            kept.push(Op::retain(op.len(), None));
+        }
+    }
+    kept.chop();
+    kept
+}
+
+/// Three-way merge of 2 documents diverging from a common `base` document
+///
+/// Each side is diffed against `base` and their changes are combined with
+/// [Delta::transform]. Edits of both sides replacing or formatting overlapping parts of
+/// `base`, or inserting at the same index, are reported as [Conflict]s and resolved in our
+/// favor: their conflicting edits are discarded, so that the merged document has our
+/// version of the whole conflicting part.
+///
+/// # Panics
+///
+/// If any [Delta] is not a document, see [Delta::diff].
+///
+/// # Example
+///
+/// ```
+/// use quill_delta_rs::{merge, Conflict, Delta, Range};
+///
+/// let base = Delta::new().insert("The fox jumps\n", None);
+/// let ours = Delta::new().insert("The red fox jumps\n", None);
+/// let theirs = Delta::new().insert("The fox jumps high\n", None);
+/// let (merged, conflicts) = merge(&base, &ours, &theirs);
+/// assert_eq!(Delta::new().insert("The red fox jumps high\n", None), merged);
+/// assert!(conflicts.is_empty());
+///
+/// let theirs = Delta::new().insert("The brown fox jumps high\n", None);
+/// let (merged, conflicts) = merge(&base, &ours, &theirs);
+/// assert_eq!(Delta::new().insert("The red fox jumps high\n", None), merged);
+/// assert_eq!(
+///     vec![Conflict {
+///         base: Range::new(4, 0),
+///         ours: Range::new(4, 4),
+///         theirs: Range::new(4, 6),
+///     }],
+///     conflicts
+/// );
+/// ```
+pub fn merge(base: &Delta, ours: &Delta, theirs: &Delta) -> (Delta, Vec<Conflict>) {
+    let our_change = base.diff(ours);
+    let their_change = base.diff(theirs);
+
+    let their_hunks = hunks(&their_change);
+    let mut overlapping = vec![false; their_hunks.len()];
+    let mut conflicts = Vec::new();
+    for our_hunk in hunks(&our_change) {
+        for (i, their_hunk) in their_hunks.iter().enumerate() {
+            if !our_hunk.overlaps(their_hunk) {
+                continue;
+            }
+            overlapping[i] = true;
+            let index = our_hunk.base.index.min(their_hunk.base.index);
+            let end = our_hunk.base.end().max(their_hunk.base.end());
+            conflicts.push(Conflict {
+                base: Range::new(index, end - index),
+                ours: our_hunk.changed,
+                theirs: their_hunk.changed,
+            });
+        }
+    }
+    let discarded: Vec<Hunk> = their_hunks
+        .into_iter()
+        .zip(overlapping)
+        .filter_map(|(hunk, overlapping)| overlapping.then_some(hunk))
+        .collect();
+    let their_change = discard(&their_change, &discarded);
+    let merged = ours.compose(&our_change.transform(&their_change, true));
+    (merged, conflicts)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{AttributesMap, Delta, Range};
+
+    use super::{Conflict, merge};
+
+    fn base() -> Delta {
+        Delta::new().insert("The quick fox jumps\n", None)
+    }
+
+    #[test]
+    fn no_changes() {
+        let (merged, conflicts) = merge(&base(), &base(), &base());
+        assert_eq!(base(), merged);
+        assert!(conflicts.is_empty());
+    }
+
+    #[test]
+    fn one_side() {
+        let ours = Delta::new().insert("The quick brown fox jumps\n", None);
+        let (merged, conflicts) = merge(&base(), &ours, &base());
+        assert_eq!(ours, merged);
+        assert!(conflicts.is_empty());
+        let (merged, conflicts) = merge(&base(), &base(), &ours);
+        assert_eq!(ours, merged);
+        assert!(conflicts.is_empty());
+    }
+
+    #[test]
+    fn disjoint_edits() {
+        let ours = Delta::new().insert("A quick fox jumps\n", None);
+        let theirs = Delta::new()
+            .insert("The quick fox ", None)
+            .insert("jumps", Some(attributes!("bold" => true)))
+            .insert("\n", None);
+        let (merged, conflicts) = merge(&base(), &ours, &theirs);
+        assert_eq!(
+            Delta::new()
+                .insert("A quick fox ", None)
+                .insert("jumps", Some(attributes!("bold" => true)))
+                .insert("\n", None),
+            merged
+        );
+        assert!(conflicts.is_empty());
+    }
+
+    #[test]
+    fn same_insert_index() {
+        let ours = Delta::new().insert("The quick red fox jumps\n", None);
+        let theirs = Delta::new().insert("The quick sly fox jumps\n", None);
+        let (merged, conflicts) = merge(&base(), &ours, &theirs);
+        assert_eq!(
+            Delta::new().insert("The quick red fox jumps\n", None),
+            merged
+        );
+        assert_eq!(
+            vec![Conflict {
+                base: Range::new(10, 0),
+                ours: Range::new(10, 4),
+                theirs: Range::new(10, 4),
+            }],
+            conflicts
+        );
+    }
+
+    #[test]
+    fn insert_into_deleted_text() {
+        let ours = Delta::new().insert("The fox jumps\n", None);
+        let theirs = Delta::new().insert("The quiet fox jumps\n", None);
+        let (merged, conflicts) = merge(&base(), &ours, &theirs);
+        assert_eq!(ours, merged);
+        assert_eq!(
+            vec![Conflict {
+                base: Range::new(4, 6),
+                ours: Range::new(4, 0),
+                theirs: Range::new(7, 2),
+            }],
+            conflicts
+        );
+    }
+
+    #[test]
+    fn conflicting_formats() {
+        let format = |color: &str| {
+            Delta::new()
+                .insert("The ", None)
+                .insert("quick", Some(attributes!("color" => color)))
+                .insert(" fox jumps\n", None)
+        };
+        let (merged, conflicts) = merge(&base(), &format("red"), &format("blue"));
+        assert_eq!(format("red"), merged);
+        assert_eq!(
+            vec![Conflict {
+                base: Range::new(4, 5),
+                ours: Range::new(4, 5),
+                theirs: Range::new(4, 5),
+            }],
+            conflicts
+        );
+    }
+
+    #[test]
+    fn adjacent_edits() {
+        let ours = Delta::new().insert("The slow fox jumps\n", None);
+        let theirs = Delta::new().insert("The quick fox leaps\n", None);
+        let (merged, conflicts) = merge(&base(), &ours, &theirs);
+        assert_eq!(Delta::new().insert("The slow fox leaps\n", None), merged);
+        assert!(conflicts.is_empty());
+    }
+}
@@ -91,6 +91,17 @@ impl Op {
         })
     }
 
+    /// Insert any value with its attributes, embeds included
+    ///
+    /// Quill allows attributes on embeds, e.g. the `width` of an image, and deserializing an
+    /// [Op] keeps them: used to carry such ops through operations without panicking.
+    pub(crate) fn insert_any(object: Value, attributes: Option<AttributesMap>) -> Self {
+        Op {
+            kind: OpType::Insert(object),
+            attributes: attributes.unwrap_or_default(),
+        }
+    }
+
     pub fn retain(length: usize, attributes: Option<AttributesMap>) -> Self {
         assert_ne!(length, 0, "retain length must be greater than zero");
         Op {
@@ -130,14 +141,15 @@ impl Op {
         matches!(self.kind, OpType::Delete(_))
     }
 
-    pub fn kind<'a>(&'a self) -> &'a OpType {
+    pub fn kind(&self) -> &OpType {
         &self.kind
     }
 
+    /// Length of the op, in characters for text inserts
     pub fn len(&self) -> usize {
         match &self.kind {
             OpType::Insert(value) => match value {
-                Value::String(s) => s.len(),
+                Value::String(s) => s.chars().count(),
                 _ => 1,
             },
             OpType::Retain(len) => *len,
@@ -149,7 +161,7 @@ impl Op {
         self.len() == 0
     }
 
-    pub fn attributes<'a>(&'a self) -> Option<&'a AttributesMap> {
+    pub fn attributes(&self) -> Option<&AttributesMap> {
         match self.kind {
             OpType::Delete(_) => None,
             _ => {
@@ -173,7 +185,7 @@ impl Op {
         }
     }
 
-    pub fn value_as_string<'a>(&'a self) -> &'a str {
+    pub fn value_as_string(&self) -> &str {
         match &self.kind {
             OpType::Insert(value) => {
                 if let Some(str) = value.as_str() {
@@ -225,7 +237,7 @@ mod tests {
 
     use serde_json::{Value, json};
 
-    use crate::{AttributesMap, attributes};
+    use crate::AttributesMap;
 
     use crate::op::{Op, OpType};
 
@@ -448,4 +460,10 @@ mod tests {
         let op = Op::retain_until_end();
         assert_eq!(op.len(), usize::MAX)
     }
+
+    #[test]
+    fn len_counts_characters() {
+        assert_eq!(Op::insert("Grüße 世界", None).len(), 8);
+        assert_eq!(Op::insert("🦊", None).len(), 1);
+    }
 }
@@ -0,0 +1,551 @@
+use pyo3::{
+    IntoPyObjectExt,
+    exceptions::{PyTypeError, PyValueError},
+    prelude::*,
+    types::{PyBool, PyDict, PyFloat, PyInt, PyList, PyString, PyTuple},
+};
+use serde_json::{Map, Number, Value};
+
+use crate::{attributes::AttributesMap, delta::Delta, json::JsonFormat, op::Op};
+
+fn value_error<E: std::fmt::Display>(error: E) -> PyErr {
+    PyValueError::new_err(error.to_string())
+}
+
+/// Convert a JSON value to the Python object of the same shape
+fn to_py<'py>(py: Python<'py>, value: &Value) -> PyResult<Bound<'py, PyAny>> {
+    match value {
+        Value::Null => Ok(py.None().into_bound(py)),
+        Value::Bool(boolean) => boolean.into_bound_py_any(py),
+        Value::Number(number) => match (number.as_u64(), number.as_i64()) {
+            (Some(number), _) => number.into_bound_py_any(py),
+            (_, Some(number)) => number.into_bound_py_any(py),
+            _ => number.as_f64().unwrap_or_default().into_bound_py_any(py),
+        },
+        Value::String(string) => string.into_bound_py_any(py),
+        Value::Array(values) => {
+            let list = PyList::empty(py);
+            for value in values {
+                list.append(to_py(py, value)?)?;
+            }
+            Ok(list.into_any())
+        }
+        Value::Object(map) => {
+            let dict = PyDict::new(py);
+            for (key, value) in map {
+                dict.set_item(key, to_py(py, value)?)?;
+            }
+            Ok(dict.into_any())
+        }
+    }
+}
+
+/// Convert a Python object made of dicts, lists and scalars to a JSON value
+fn from_py(object: &Bound<'_, PyAny>) -> PyResult<Value> {
+    if object.is_none() {
+        return Ok(Value::Null);
+    }
+    // Checked before ints, of which bools are a subclass
+    if let Ok(boolean) = object.downcast::<PyBool>() {
+        return Ok(Value::Bool(boolean.is_true()));
+    }
+    if object.is_instance_of::<PyInt>() {
+        return match object.extract::<u64>() {
+            Ok(number) => Ok(Value::from(number)),
+            Err(_) => Ok(Value::from(object.extract::<i64>()?)),
+        };
+    }
+    if let Ok(number) = object.downcast::<PyFloat>() {
+        return Number::from_f64(number.value())
+            .map(Value::Number)
+            .ok_or_else(|| PyValueError::new_err("non finite float"));
+    }
+    if let Ok(string) = object.downcast::<PyString>() {
+        return Ok(Value::String(string.to_str()?.to_string()));
+    }
+    if let Ok(attributes) = object.downcast::<PyAttributesMap>() {
+        return serde_json::to_value(&attributes.borrow().attributes).map_err(value_error);
+    }
+    if let Ok(op) = object.downcast::<PyOp>() {
+        return serde_json::to_value(&op.borrow().op).map_err(value_error);
+    }
+    if object.is_instance_of::<PyList>() || object.is_instance_of::<PyTuple>() {
+        return object.try_iter()?.map(|item| from_py(&item?)).collect();
+    }
+    if let Ok(dict) = object.downcast::<PyDict>() {
+        let mut map = Map::new();
+        for (key, value) in dict {
+            let key = key
+                .downcast::<PyString>()
+                .map_err(|_| PyTypeError::new_err("dict keys should be strings"))?;
+            map.insert(key.to_str()?.to_string(), from_py(&value)?);
+        }
+        return Ok(Value::Object(map));
+    }
+    Err(PyTypeError::new_err(format!(
+        "can't convert {} to JSON",
+        object.get_type().name()?
+    )))
+}
+
+fn attributes(object: Option<&Bound<'_, PyAny>>) -> PyResult<Option<AttributesMap>> {
+    match object {
+        Some(object) if !object.is_none() => Ok(Some(
+            serde_json::from_value(from_py(object)?).map_err(value_error)?,
+        )),
+        _ => Ok(None),
+    }
+}
+
+/// [AttributesMap] exposed to Python, available with the `python` feature
+#[pyclass(name = "AttributesMap", eq)]
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct PyAttributesMap {
+    attributes: AttributesMap,
+}
+
+#[pymethods]
+impl PyAttributesMap {
+    #[new]
+    #[pyo3(signature = (attributes=None))]
+    fn new(attributes: Option<&Bound<'_, PyAny>>) -> PyResult<Self> {
+        Ok(PyAttributesMap {
+            attributes: self::attributes(attributes)?.unwrap_or_default(),
+        })
+    }
+
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        to_py(
+            py,
+            &serde_json::to_value(&self.attributes).map_err(value_error)?,
+        )
+    }
+
+    fn __len__(&self) -> usize {
+        (&self.attributes).into_iter().count()
+    }
+
+    fn __contains__(&self, key: &str) -> bool {
+        self.attributes.get(key).is_some()
+    }
+
+    fn __getitem__<'py>(&self, py: Python<'py>, key: &str) -> PyResult<Bound<'py, PyAny>> {
+        match self.attributes.get(key) {
+            Some(value) => to_py(py, value),
+            None => Err(pyo3::exceptions::PyKeyError::new_err(key.to_string())),
+        }
+    }
+
+    fn __setitem__(&mut self, key: &str, value: &Bound<'_, PyAny>) -> PyResult<()> {
+        self.attributes.insert(key, from_py(value)?);
+        Ok(())
+    }
+
+    fn __repr__(&self) -> String {
+        format!("AttributesMap({})", self.attributes)
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (a, b, keep_null=false))]
+    fn compose(a: &Self, b: &Self, keep_null: bool) -> Option<Self> {
+        AttributesMap::compose(a.attributes.clone(), b.attributes.clone(), keep_null)
+            .map(|attributes| PyAttributesMap { attributes })
+    }
+
+    #[staticmethod]
+    fn diff(a: &Self, b: &Self) -> Option<Self> {
+        AttributesMap::diff(a.attributes.clone(), b.attributes.clone())
+            .map(|attributes| PyAttributesMap { attributes })
+    }
+
+    #[staticmethod]
+    fn invert(attributes: &Self, base: &Self) -> Self {
+        PyAttributesMap {
+            attributes: AttributesMap::invert(
+                attributes.attributes.clone(),
+                base.attributes.clone(),
+            ),
+        }
+    }
+}
+
+/// [Op] exposed to Python, available with the `python` feature
+#[pyclass(name = "Op", eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PyOp {
+    op: Op,
+}
+
+#[pymethods]
+impl PyOp {
+    /// Create an op from its dict, e.g. `{"insert": "Hello", "attributes": {"bold": True}}`
+    #[new]
+    fn new(op: &Bound<'_, PyAny>) -> PyResult<Self> {
+        Ok(PyOp {
+            op: serde_json::from_value(from_py(op)?).map_err(value_error)?,
+        })
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (value, attributes=None))]
+    fn insert(value: &Bound<'_, PyAny>, attributes: Option<&Bound<'_, PyAny>>) -> PyResult<Self> {
+        Ok(PyOp {
+            op: Op::insert_any(from_py(value)?, self::attributes(attributes)?),
+        })
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (length, attributes=None))]
+    fn retain(length: usize, attributes: Option<&Bound<'_, PyAny>>) -> PyResult<Self> {
+        if length == 0 {
+            return Err(PyValueError::new_err(
+                "retain length must be greater than zero",
+            ));
+        }
+        Ok(PyOp {
+            op: Op::retain(length, self::attributes(attributes)?),
+        })
+    }
+
+    #[staticmethod]
+    fn delete(length: usize) -> PyResult<Self> {
+        if length == 0 {
+            return Err(PyValueError::new_err(
+                "delete length must be greater than zero",
+            ));
+        }
+        Ok(PyOp {
+            op: Op::delete(length),
+        })
+    }
+
+    fn is_insert(&self) -> bool {
+        self.op.is_insert()
+    }
+
+    fn is_retain(&self) -> bool {
+        self.op.is_retain()
+    }
+
+    fn is_delete(&self) -> bool {
+        self.op.is_delete()
+    }
+
+    fn __len__(&self) -> usize {
+        self.op.len()
+    }
+
+    /// The inserted text or embed, [None] for retains and deletes
+    #[getter]
+    fn value<'py>(&self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyAny>>> {
+        if !self.op.is_insert() {
+            return Ok(None);
+        }
+        to_py(py, &self.op.value()).map(Some)
+    }
+
+    #[getter]
+    fn attributes(&self) -> Option<PyAttributesMap> {
+        self.op.attributes().map(|attributes| PyAttributesMap {
+            attributes: attributes.clone(),
+        })
+    }
+
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        to_py(py, &serde_json::to_value(&self.op).map_err(value_error)?)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Op({})",
+            serde_json::to_string(&self.op).unwrap_or_default()
+        )
+    }
+}
+
+/// [Delta] exposed to Python, available with the `python` feature
+///
+/// Created from a list of ops, as [PyOp]s or dicts, or a dict with an `ops` or `delta` key,
+/// and exported back to dicts and lists of the same shape as quill-delta's JSON, or to HTML.
+#[pyclass(name = "Delta", eq)]
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct PyDelta {
+    delta: Delta,
+}
+
+#[pymethods]
+impl PyDelta {
+    #[new]
+    #[pyo3(signature = (ops=None))]
+    fn new(ops: Option<&Bound<'_, PyAny>>) -> PyResult<Self> {
+        let delta = match ops {
+            Some(ops) if !ops.is_none() => {
+                serde_json::from_value(from_py(ops)?).map_err(value_error)?
+            }
+            _ => Delta::new(),
+        };
+        Ok(PyDelta { delta })
+    }
+
+    /// Parse a JSON string in any [JsonFormat]
+    #[staticmethod]
+    fn from_json(json: &str) -> PyResult<Self> {
+        Ok(PyDelta {
+            delta: serde_json::from_str(json).map_err(value_error)?,
+        })
+    }
+
+    /// Serialize as a JSON string, `{"ops": [...]}` as quill-delta
+    fn to_json(&self) -> PyResult<String> {
+        serde_json::to_string(&self.delta.json(JsonFormat::Ops)).map_err(value_error)
+    }
+
+    /// `{"ops": [...]}`
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let value = serde_json::to_value(self.delta.json(JsonFormat::Ops));
+        to_py(py, &value.map_err(value_error)?)
+    }
+
+    /// The list of the dicts of the ops
+    #[getter]
+    fn ops<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        to_py(
+            py,
+            &serde_json::to_value(self.delta.ops()).map_err(value_error)?,
+        )
+    }
+
+    fn push<'py>(mut slf: PyRefMut<'py, Self>, op: &PyOp) -> PyRefMut<'py, Self> {
+        slf.delta.push(op.op.clone());
+        slf
+    }
+
+    /// Insert text or an embed, both with attributes, ignoring empty text
+    #[pyo3(signature = (value, attributes=None))]
+    fn insert<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        value: &Bound<'_, PyAny>,
+        attributes: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        let value = from_py(value)?;
+        if value.is_null() || value.as_str() == Some("") {
+            return Ok(slf);
+        }
+        slf.delta
+            .push(Op::insert_any(value, self::attributes(attributes)?));
+        Ok(slf)
+    }
+
+    /// Retain `length` characters, ignoring a length of 0
+    #[pyo3(signature = (length, attributes=None))]
+    fn retain<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        length: usize,
+        attributes: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        if length > 0 {
+            let op = Op::retain(length, self::attributes(attributes)?);
+            slf.delta.push(op);
+        }
+        Ok(slf)
+    }
+
+    /// Delete `length` characters, ignoring a length of 0
+    fn delete(mut slf: PyRefMut<'_, Self>, length: usize) -> PyRefMut<'_, Self> {
+        if length > 0 {
+            slf.delta.push(Op::delete(length));
+        }
+        slf
+    }
+
+    fn compose(&self, other: &Self) -> Self {
+        PyDelta {
+            delta: self.delta.compose(&other.delta),
+        }
+    }
+
+    fn invert(&self, base: &Self) -> Self {
+        PyDelta {
+            delta: self.delta.invert(&base.delta),
+        }
+    }
+
+    #[pyo3(signature = (start=0, end=None))]
+    fn slice(&self, start: usize, end: Option<usize>) -> Self {
+        PyDelta {
+            delta: self.delta.slice(start, end),
+        }
+    }
+
+    fn length(&self) -> usize {
+        self.delta.len()
+    }
+
+    /// Negative if more is deleted than inserted
+    fn change_length(&self) -> i64 {
+        self.delta
+            .ops()
+            .iter()
+            .fold(0, |length, op| match (op.is_insert(), op.is_delete()) {
+                (true, _) => length + op.len() as i64,
+                (_, true) => length - op.len() as i64,
+                _ => length,
+            })
+    }
+
+    fn __len__(&self) -> usize {
+        self.delta.len()
+    }
+
+    fn plain_text(&self) -> String {
+        self.delta.plain_text()
+    }
+
+    /// HTML of the document, see [Delta::html]
+    fn to_html(&self) -> String {
+        self.delta.to_html()
+    }
+
+    fn __repr__(&self) -> PyResult<String> {
+        Ok(format!("Delta({})", self.to_json()?))
+    }
+}
+
+impl From<Delta> for PyDelta {
+    fn from(delta: Delta) -> Self {
+        PyDelta { delta }
+    }
+}
+
+impl From<PyDelta> for Delta {
+    fn from(delta: PyDelta) -> Self {
+        delta.delta
+    }
+}
+
+/// The `quill_delta_rs` Python module
+#[pymodule]
+#[pyo3(name = "quill_delta_rs")]
+fn python_module(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyAttributesMap>()?;
+    module.add_class::<PyOp>()?;
+    module.add_class::<PyDelta>()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pyo3::prelude::*;
+    use serde_json::json;
+
+    use crate::{AttributesMap, Delta};
+
+    use super::{PyAttributesMap, PyDelta, PyOp, from_py, to_py};
+
+    fn with_python<F: for<'py> FnOnce(Python<'py>) -> PyResult<()>>(f: F) {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(f).unwrap();
+    }
+
+    #[test]
+    fn convert() {
+        with_python(|py| {
+            let value = json!({
+                "none": null,
+                "bool": true,
+                "int": -3,
+                "big": u64::MAX,
+                "float": 1.5,
+                "list": ["a", {"b": [false]}],
+            });
+            let object = to_py(py, &value)?;
+            assert!(
+                object
+                    .get_item("bool")?
+                    .is_instance_of::<pyo3::types::PyBool>()
+            );
+            assert_eq!(value, from_py(&object)?);
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn delta() {
+        with_python(|py| {
+            let ops = to_py(
+                py,
+                &json!([{"insert": "Hello", "attributes": {"bold": true}}]),
+            )?;
+            let document = PyDelta::new(Some(&ops))?;
+            assert_eq!(
+                Delta::new().insert("Hello", Some(attributes!("bold" => true))),
+                Delta::from(document.clone())
+            );
+            let ops = to_py(py, &json!({"ops": [{"retain": 5}, {"insert": " World"}]}))?;
+            let change = PyDelta::new(Some(&ops))?;
+
+            let composed = document.compose(&change);
+            assert_eq!(11, composed.length());
+            assert_eq!(6, change.change_length());
+            assert_eq!(-6, change.invert(&document).change_length());
+            assert_eq!("Hello World", composed.plain_text());
+            assert_eq!("<p><b>Hello</b> World</p>", composed.to_html());
+            assert_eq!(document, composed.compose(&change.invert(&document)));
+            assert_eq!("World", composed.slice(6, None).plain_text());
+            assert_eq!(
+                json!({"ops": [
+                    {"insert": "Hello", "attributes": {"bold": true}},
+                    {"insert": " World"}
+                ]}),
+                from_py(&composed.to_dict(py)?)?
+            );
+            assert!(PyDelta::new(Some(&to_py(py, &json!({"ops": 1}))?)).is_err());
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn builders() {
+        with_python(|py| {
+            let delta = Bound::new(py, PyDelta::default())?;
+            let bold = to_py(py, &json!({"bold": true}))?;
+            PyDelta::insert(delta.borrow_mut(), &to_py(py, &json!("\n"))?, None)?;
+            PyDelta::retain(delta.borrow_mut(), 2, Some(&bold))?;
+            PyDelta::delete(delta.borrow_mut(), 0);
+            assert_eq!(
+                Delta::from(vec![
+                    crate::Op::insert("\n", None),
+                    crate::Op::retain(2, Some(attributes!("bold" => true))),
+                ]),
+                Delta::from(delta.borrow().clone())
+            );
+
+            let embed = to_py(py, &json!({"image": "fox.png"}))?;
+            assert_eq!(
+                json!({"insert": {"image": "fox.png"}, "attributes": {"bold": true}}),
+                from_py(&PyOp::insert(&embed, Some(&bold))?.to_dict(py)?)?
+            );
+            PyDelta::insert(delta.borrow_mut(), &embed, Some(&bold))?;
+            assert_eq!(
+                Some(&attributes!("bold" => true)),
+                delta.borrow().delta.ops()[2].attributes()
+            );
+            assert!(PyOp::retain(0, None).is_err());
+            let op = PyOp::new(&to_py(
+                py,
+                &json!({"retain": 2, "attributes": {"bold": true}}),
+            )?)?;
+            assert_eq!(
+                Some(PyAttributesMap {
+                    attributes: attributes!("bold" => true)
+                }),
+                op.attributes()
+            );
+            assert_eq!(
+                json!({"retain": 2, "attributes": {"bold": true}}),
+                from_py(&op.to_dict(py)?)?
+            );
+            Ok(())
+        });
+    }
+}
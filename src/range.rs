@@ -0,0 +1,142 @@
+use serde::{Deserialize, Serialize};
+
+use crate::delta::Delta;
+
+/// A range of a document, such as a selection
+///
+/// Serializes as Quill's selection JSON: `{"index": 2, "length": 3}`.
+#[derive(Default, Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Range {
+    pub index: usize,
+    pub length: usize,
+}
+
+impl Range {
+    pub fn new(index: usize, length: usize) -> Self {
+        Range { index, length }
+    }
+
+    /// Exclusive end index of the range
+    pub fn end(&self) -> usize {
+        self.index + self.length
+    }
+
+    /// Whether the range has no length, like a cursor
+    pub fn is_collapsed(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Transform the range against a change
+    ///
+    /// Both ends are transformed with [Delta::transform_position]; a range whose content is
+    /// deleted collapses at the deletion.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use quill_delta_rs::{Delta, Range};
+    ///
+    /// let selection = Range::new(4, 3);
+    /// let change = Delta::new().insert("Hi ", None);
+    /// assert_eq!(Range::new(7, 3), selection.transform(&change, false));
+    ///
+    /// let change = Delta::new().retain(3, None).delete(5);
+    /// assert_eq!(Range::new(3, 0), selection.transform(&change, false));
+    /// ```
+    pub fn transform(&self, change: &Delta, priority: bool) -> Range {
+        let index = change.transform_position(self.index, priority);
+        let end = change.transform_position(self.end(), priority);
+        Range {
+            index,
+            length: end.saturating_sub(index),
+        }
+    }
+}
+
+impl From<(usize, usize)> for Range {
+    fn from((index, length): (usize, usize)) -> Self {
+        Range { index, length }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::Delta;
+
+    use super::Range;
+
+    #[test]
+    fn end() {
+        assert_eq!(5, Range::new(2, 3).end());
+        assert!(Range::new(2, 0).is_collapsed());
+        assert!(!Range::new(2, 3).is_collapsed());
+    }
+
+    #[test]
+    fn serialize() {
+        assert_eq!(
+            json!({"index": 2, "length": 3}),
+            serde_json::to_value(Range::new(2, 3)).unwrap()
+        );
+    }
+
+    #[test]
+    fn deserialize() {
+        let range: Range = serde_json::from_value(json!({"index": 2, "length": 3})).unwrap();
+        assert_eq!(Range::new(2, 3), range);
+        let range: Option<Range> = serde_json::from_value(json!(null)).unwrap();
+        assert_eq!(None, range);
+    }
+
+    #[test]
+    fn transform_insert_before() {
+        let change = Delta::new().retain(1, None).insert("ab", None);
+        assert_eq!(Range::new(4, 3), Range::new(2, 3).transform(&change, false));
+    }
+
+    #[test]
+    fn transform_insert_inside() {
+        let change = Delta::new().retain(3, None).insert("ab", None);
+        assert_eq!(Range::new(2, 5), Range::new(2, 3).transform(&change, false));
+    }
+
+    #[test]
+    fn transform_insert_at_edges() {
+        let change = Delta::new().retain(2, None).insert("ab", None);
+        assert_eq!(Range::new(4, 3), Range::new(2, 3).transform(&change, false));
+        assert_eq!(Range::new(2, 5), Range::new(2, 3).transform(&change, true));
+        let change = Delta::new().retain(5, None).insert("ab", None);
+        assert_eq!(Range::new(2, 5), Range::new(2, 3).transform(&change, false));
+        assert_eq!(Range::new(2, 3), Range::new(2, 3).transform(&change, true));
+    }
+
+    #[test]
+    fn transform_collapsed() {
+        let change = Delta::new().retain(2, None).insert("ab", None);
+        assert_eq!(Range::new(4, 0), Range::new(2, 0).transform(&change, false));
+        assert_eq!(Range::new(2, 0), Range::new(2, 0).transform(&change, true));
+    }
+
+    #[test]
+    fn transform_delete_overlapping() {
+        let change = Delta::new().retain(3, None).delete(4);
+        assert_eq!(Range::new(2, 1), Range::new(2, 3).transform(&change, false));
+        let change = Delta::new().retain(1, None).delete(2);
+        assert_eq!(Range::new(1, 2), Range::new(2, 3).transform(&change, false));
+    }
+
+    #[test]
+    fn transform_delete_all() {
+        let change = Delta::new().retain(1, None).delete(6);
+        assert_eq!(Range::new(1, 0), Range::new(2, 3).transform(&change, false));
+    }
+
+    #[test]
+    fn transform_replace() {
+        let change = Delta::new().retain(2, None).insert("xyz", None).delete(3);
+        assert_eq!(Range::new(5, 0), Range::new(2, 3).transform(&change, false));
+        assert_eq!(Range::new(2, 3), Range::new(2, 3).transform(&change, true));
+    }
+}
@@ -0,0 +1,250 @@
+use serde_json::json;
+
+use crate::{attributes::AttributesMap, delta::Delta, iter::Iterator, op::Op};
+
+/// Attribute of text only present in the new document of a redline
+pub const ADDED: &str = "added";
+/// Attribute of text only present in the old document of a redline
+pub const REMOVED: &str = "removed";
+/// Attribute of text whose formats changed, set to `{"before": {..}, "after": {..}}`
+pub const CHANGED: &str = "changed";
+
+fn tagged(op: &Op, attributes: AttributesMap, key: &str, value: serde_json::Value) -> Op {
+    let mut attributes = attributes;
+    attributes.insert(key, value);
+    Op::insert_any(op.value(), Some(attributes))
+}
+
+impl Delta {
+    /// Get the redline of this document [Delta] against a newer version of it
+    ///
+    /// Returns a document merging both versions for display: content inserted in `other`
+    /// has the [ADDED] attribute, content removed from this document is kept with the
+    /// [REMOVED] attribute, before the content replacing it if any, and content whose
+    /// formats changed has the formats of `other` and the [CHANGED] attribute, set to the
+    /// formats before and after. Unchanged content is left as is. Embeds are tagged like
+    /// text, a replaced embed being shown removed then added.
+    ///
+    /// # Panics
+    ///
+    /// If either [Delta] is not a document, see [Delta::diff].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use quill_delta_rs::{Delta, Op, {attributes, AttributesMap}};
+    /// use serde_json::json;
+    ///
+    /// let old = Delta::from(vec![Op::insert("The red fox\n", None)]);
+    /// let new = Delta::from(vec![
+    ///     Op::insert("The quick ", None),
+    ///     Op::insert("fox", Some(attributes!("bold" => true))),
+    ///     Op::insert("\n", None),
+    /// ]);
+    /// assert_eq!(
+    ///     Delta::from(vec![
+    ///         Op::insert("The ", None),
+    ///         Op::insert("red", Some(attributes!("removed" => true))),
+    ///         Op::insert("quick", Some(attributes!("added" => true))),
+    ///         Op::insert(" ", None),
+    ///         Op::insert("fox", Some(attributes!(
+    ///             "bold" => true,
+    ///             "changed" => json!({"before": {}, "after": {"bold": true}})
+    ///         ))),
+    ///         Op::insert("\n", None),
+    ///     ]),
+    ///     old.redline(&new)
+    /// );
+    /// ```
+    pub fn redline(&self, other: &Delta) -> Delta {
+        let change = self.diff(other);
+        let mut iter = Iterator::from(self.ops().clone());
+        let mut redline = Delta::new();
+        // Added text goes after the text it replaces
+        let mut added: Vec<Op> = Vec::new();
+        for op in change.ops() {
+            if op.is_insert() {
+                let attributes = op.attributes().cloned().unwrap_or_default();
+                added.push(tagged(op, attributes, ADDED, json!(true)));
+                continue;
+            }
+            if op.is_retain() {
+                for op in added.drain(..) {
+                    redline.push(op);
+                }
+            }
+            let mut length = op.len();
+            while length > 0 && iter.has_next() {
+                let old = iter.next_len(length);
+                length -= old.len();
+                let before = old.attributes().cloned().unwrap_or_default();
+                if op.is_delete() {
+                    redline.push(tagged(&old, before, REMOVED, json!(true)));
+                } else if let Some(diff) = op.attributes() {
+                    let after = AttributesMap::compose(before.clone(), diff.clone(), false)
+                        .unwrap_or_default();
+                    let changed = json!({"before": before, "after": after});
+                    redline.push(tagged(&old, after, CHANGED, changed));
+                } else {
+                    redline.push(old);
+                }
+            }
+        }
+        for op in added {
+            redline.push(op);
+        }
+        for op in iter.rest() {
+            redline.push(op);
+        }
+        redline
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{AttributesMap, Delta, Op};
+
+    #[test]
+    fn identical() {
+        let doc = Delta::new().insert("The fox\n", None);
+        assert_eq!(doc, doc.redline(&doc));
+    }
+
+    #[test]
+    fn added() {
+        let old = Delta::new().insert("The fox\n", None);
+        let new = Delta::new().insert("The fox jumps\n", None);
+        assert_eq!(
+            Delta::from(vec![
+                Op::insert("The fox", None),
+                Op::insert(" jumps", Some(attributes!("added" => true))),
+                Op::insert("\n", None),
+            ]),
+            old.redline(&new)
+        );
+    }
+
+    #[test]
+    fn removed_keeps_formats() {
+        let old = Delta::new()
+            .insert("The ", None)
+            .insert("red", Some(attributes!("italic" => true)))
+            .insert(" fox\n", None);
+        let new = Delta::new().insert("The  fox\n", None);
+        assert_eq!(
+            Delta::from(vec![
+                Op::insert("The ", None),
+                Op::insert(
+                    "red",
+                    Some(attributes!("italic" => true, "removed" => true))
+                ),
+                Op::insert(" fox\n", None),
+            ]),
+            old.redline(&new)
+        );
+    }
+
+    #[test]
+    fn removed_format() {
+        let old = Delta::from(vec![
+            Op::insert("fox", Some(attributes!("bold" => true, "color" => "red"))),
+            Op::insert("\n", None),
+        ]);
+        let new = Delta::from(vec![
+            Op::insert("fox", Some(attributes!("color" => "red"))),
+            Op::insert("\n", None),
+        ]);
+        assert_eq!(
+            Delta::from(vec![
+                Op::insert(
+                    "fox",
+                    Some(attributes!(
+                        "color" => "red",
+                        "changed" => json!({
+                            "before": {"bold": true, "color": "red"},
+                            "after": {"color": "red"}
+                        })
+                    ))
+                ),
+                Op::insert("\n", None),
+            ]),
+            old.redline(&new)
+        );
+    }
+
+    #[test]
+    fn line_format() {
+        let old = Delta::from(vec![Op::insert("Title\n", None)]);
+        let new = Delta::from(vec![
+            Op::insert("Title", None),
+            Op::insert("\n", Some(attributes!("header" => 1))),
+        ]);
+        assert_eq!(
+            Delta::from(vec![
+                Op::insert("Title", None),
+                Op::insert(
+                    "\n",
+                    Some(attributes!(
+                        "header" => 1,
+                        "changed" => json!({"before": {}, "after": {"header": 1}})
+                    ))
+                ),
+            ]),
+            old.redline(&new)
+        );
+    }
+
+    #[test]
+    fn embeds() {
+        let old = Delta::from(vec![
+            Op::insert(json!({"image": "fox.png"}), None),
+            Op::insert("\n", None),
+        ]);
+        let new = Delta::from(vec![
+            Op::insert(json!({"image": "cat.png"}), None),
+            Op::insert("\n", None),
+        ]);
+        assert_eq!(
+            Delta::from(vec![
+                Op::insert_any(
+                    json!({"image": "fox.png"}),
+                    Some(attributes!("removed" => true))
+                ),
+                Op::insert_any(
+                    json!({"image": "cat.png"}),
+                    Some(attributes!("added" => true))
+                ),
+                Op::insert("\n", None),
+            ]),
+            old.redline(&new)
+        );
+    }
+
+    #[test]
+    fn embed_format() {
+        let image = json!({"image": "fox.png"});
+        let old = Delta::from(vec![
+            Op::insert(image.clone(), None),
+            Op::insert("\n", None),
+        ]);
+        let new = Delta::from(vec![
+            Op::insert_any(image.clone(), Some(attributes!("width" => "120"))),
+            Op::insert("\n", None),
+        ]);
+        assert_eq!(
+            Delta::from(vec![
+                Op::insert_any(
+                    image,
+                    Some(attributes!(
+                        "width" => "120",
+                        "changed" => json!({"before": {}, "after": {"width": "120"}})
+                    ))
+                ),
+                Op::insert("\n", None),
+            ]),
+            old.redline(&new)
+        );
+    }
+}
@@ -0,0 +1,471 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::{
+    attributes::AttributesMap,
+    delta::Delta,
+    op::{Op, OpType},
+};
+
+/// Check applied to the value of an allowed attribute or embed
+#[derive(Debug, Clone)]
+pub enum Rule {
+    /// Any value
+    Any,
+    /// One of the listed values
+    OneOf(Vec<Value>),
+    /// A string URL using the `http`, `https` or `mailto` scheme
+    Link,
+    /// A string CSS color: hexadecimal, `rgb()`, `rgba()`, `hsl()`, `hsla()` or a named color
+    Color,
+    /// Custom check
+    Custom(fn(&Value) -> bool),
+}
+
+impl Rule {
+    /// Whether the value passes the check
+    pub fn check(&self, value: &Value) -> bool {
+        match self {
+            Rule::Any => true,
+            Rule::OneOf(values) => values.contains(value),
+            Rule::Link => value.as_str().is_some_and(is_link),
+            Rule::Color => value.as_str().is_some_and(is_color),
+            Rule::Custom(check) => check(value),
+        }
+    }
+}
+
+fn is_link(url: &str) -> bool {
+    let lowercase = url.to_ascii_lowercase();
+    ["http://", "https://", "mailto:"]
+        .iter()
+        .any(|scheme| lowercase.starts_with(scheme))
+        && !url.chars().any(char::is_control)
+}
+
+fn is_color(color: &str) -> bool {
+    if let Some(hex) = color.strip_prefix('#') {
+        return matches!(hex.len(), 3 | 4 | 6 | 8) && hex.chars().all(|c| c.is_ascii_hexdigit());
+    }
+    for function in ["rgb(", "rgba(", "hsl(", "hsla("] {
+        if let Some(args) = color.strip_prefix(function) {
+            return args.strip_suffix(')').is_some_and(|args| {
+                args.chars()
+                    .all(|c| c.is_ascii_digit() || matches!(c, ',' | '.' | '%' | ' ' | '/'))
+            });
+        }
+    }
+    !color.is_empty() && color.len() <= 32 && color.chars().all(|c| c.is_ascii_alphabetic())
+}
+
+/// Content removed by a [Sanitizer]
+///
+/// `index` is the position in the sanitized [Delta] of the [Op] the content was removed from,
+/// counting the characters inserted or retained before it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Stripped {
+    /// Attribute whose key is not allowed
+    UnknownAttribute { index: usize, key: String },
+    /// Attribute whose value did not pass its [Rule]
+    InvalidValue {
+        index: usize,
+        key: String,
+        value: Value,
+    },
+    /// Embed whose type is not allowed or whose value did not pass its [Rule]
+    Embed { index: usize, value: Value },
+}
+
+/// Allow-list of attributes and embeds for untrusted [Delta]s
+///
+/// # Example
+///
+/// ```
+/// use quill_delta_rs::{Delta, Op, Rule, Sanitizer, Stripped, {attributes, AttributesMap}};
+/// use serde_json::Value;
+///
+/// let sanitizer = Sanitizer::new()
+///     .allow("bold", Rule::OneOf(vec![Value::from(true)]))
+///     .allow("link", Rule::Link);
+/// let delta = Delta::from(vec![Op::insert(
+///     "click",
+///     Some(attributes!("bold" => true, "link" => "javascript:alert(1)")),
+/// )]);
+/// let (sanitized, stripped) = sanitizer.sanitize(&delta);
+/// assert_eq!(
+///     Delta::from(vec![Op::insert("click", Some(attributes!("bold" => true)))]),
+///     sanitized
+/// );
+/// assert_eq!(
+///     vec![Stripped::InvalidValue {
+///         index: 0,
+///         key: "link".to_string(),
+///         value: Value::from("javascript:alert(1)"),
+///     }],
+///     stripped
+/// );
+/// ```
+#[derive(Default, Debug, Clone)]
+pub struct Sanitizer {
+    attributes: HashMap<String, Rule>,
+    embeds: HashMap<String, Rule>,
+}
+
+impl Sanitizer {
+    /// Create a [Sanitizer] that allows no attribute and no embed.
+    pub fn new() -> Self {
+        Sanitizer {
+            attributes: HashMap::new(),
+            embeds: HashMap::new(),
+        }
+    }
+
+    /// Sanitizer allowing Quill's standard formats with validated values and image embeds
+    /// with an `http` or `https` source.
+    pub fn quill() -> Self {
+        let flag = || Rule::OneOf(vec![Value::Bool(true)]);
+        let strings =
+            |values: &[&str]| Rule::OneOf(values.iter().map(|v| Value::from(*v)).collect());
+        Sanitizer::new()
+            .allow("bold", flag())
+            .allow("italic", flag())
+            .allow("underline", flag())
+            .allow("strike", flag())
+            .allow("code", flag())
+            .allow("script", strings(&["sub", "super"]))
+            .allow("link", Rule::Link)
+            .allow("color", Rule::Color)
+            .allow("background", Rule::Color)
+            .allow("size", strings(&["small", "large", "huge"]))
+            .allow("header", Rule::OneOf((1..=6).map(Value::from).collect()))
+            .allow(
+                "list",
+                strings(&["ordered", "bullet", "checked", "unchecked"]),
+            )
+            .allow("indent", Rule::OneOf((1..=8).map(Value::from).collect()))
+            .allow("align", strings(&["center", "right", "justify"]))
+            .allow("blockquote", flag())
+            .allow("code-block", Rule::Any)
+            .allow_embed(
+                "image",
+                Rule::Custom(|src| {
+                    src.as_str().is_some_and(|src| {
+                        let src = src.to_ascii_lowercase();
+                        src.starts_with("http://") || src.starts_with("https://")
+                    })
+                }),
+            )
+    }
+
+    /// Allow an attribute whose value passes `rule`
+    pub fn allow<K: Into<String>>(mut self, key: K, rule: Rule) -> Self {
+        self.attributes.insert(key.into(), rule);
+        self
+    }
+
+    /// Allow an embed type whose value passes `rule`
+    pub fn allow_embed<K: Into<String>>(mut self, key: K, rule: Rule) -> Self {
+        self.embeds.insert(key.into(), rule);
+        self
+    }
+
+    /// Remove every attribute and embed that is not allowed from a [Delta]
+    ///
+    /// [Value::Null] is accepted for allowed attributes since it removes a format. Empty
+    /// ops, such as a deserialized `{"retain": 0}`, are dropped.
+    /// Returns the sanitized [Delta] along with what was removed.
+    pub fn sanitize(&self, delta: &Delta) -> (Delta, Vec<Stripped>) {
+        let mut sanitized = Delta::new();
+        let mut stripped = Vec::new();
+        let mut index = 0;
+        for op in delta.ops().iter().filter(|op| !op.is_empty()) {
+            match op.kind() {
+                OpType::Insert(Value::String(text)) => {
+                    let attributes =
+                        self.sanitize_attributes(op.attributes(), index, &mut stripped);
+                    sanitized.push(Op::insert(text.as_str(), attributes));
+                    index += op.len();
+                }
+                OpType::Insert(value) => {
+                    if !self.allows_embed(value) {
+                        stripped.push(Stripped::Embed {
+                            index,
+                            value: value.clone(),
+                        });
+                        continue;
+                    }
+                    let attributes =
+                        self.sanitize_attributes(op.attributes(), index, &mut stripped);
+                    sanitized.push(Op::insert_any(value.clone(), attributes));
+                    index += op.len();
+                }
+                OpType::Retain(length) => {
+                    let attributes =
+                        self.sanitize_attributes(op.attributes(), index, &mut stripped);
+                    sanitized.push(Op::retain(*length, attributes));
+                    index += length;
+                }
+                OpType::Delete(_) => {
+                    sanitized.push(op.clone());
+                }
+            }
+        }
+        (sanitized, stripped)
+    }
+
+    fn allows_embed(&self, value: &Value) -> bool {
+        match value {
+            Value::Object(embed) => {
+                !embed.is_empty()
+                    && embed.iter().all(|(key, value)| {
+                        self.embeds.get(key).is_some_and(|rule| rule.check(value))
+                    })
+            }
+            _ => false,
+        }
+    }
+
+    fn sanitize_attributes(
+        &self,
+        attributes: Option<&AttributesMap>,
+        index: usize,
+        stripped: &mut Vec<Stripped>,
+    ) -> Option<AttributesMap> {
+        let mut sanitized = AttributesMap::new();
+        for (key, value) in attributes.into_iter().flatten() {
+            match self.attributes.get(&key) {
+                None => stripped.push(Stripped::UnknownAttribute { index, key }),
+                Some(rule) if !value.is_null() && !rule.check(&value) => {
+                    stripped.push(Stripped::InvalidValue { index, key, value })
+                }
+                Some(_) => {
+                    sanitized.insert(key, value);
+                }
+            }
+        }
+        if sanitized.is_empty() {
+            None
+        } else {
+            Some(sanitized)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Value, json};
+
+    use crate::{AttributesMap, Delta, Op};
+
+    use super::{Rule, Sanitizer, Stripped, is_color, is_link};
+
+    #[test]
+    fn links() {
+        assert!(is_link("https://quilljs.com"));
+        assert!(is_link("HTTP://quilljs.com"));
+        assert!(is_link("mailto:someone@quilljs.com"));
+        assert!(!is_link("javascript:alert(1)"));
+        assert!(!is_link(" https://quilljs.com"));
+        assert!(!is_link("https://quill\njs.com"));
+        assert!(!is_link("data:text/html;base64,PHNjcmlwdD4="));
+    }
+
+    #[test]
+    fn colors() {
+        assert!(is_color("#fff"));
+        assert!(is_color("#e60000"));
+        assert!(is_color("#e60000ff"));
+        assert!(is_color("rgb(230, 0, 0)"));
+        assert!(is_color("hsla(120, 100%, 50%, 0.3)"));
+        assert!(is_color("red"));
+        assert!(!is_color("#ggg"));
+        assert!(!is_color("#12345"));
+        assert!(!is_color("red; background: url(x)"));
+        assert!(!is_color("rgb(0, 0, 0"));
+        assert!(!is_color("url(https://quilljs.com)"));
+        assert!(!is_color(""));
+    }
+
+    #[test]
+    fn keeps_allowed_content() {
+        let delta = Delta::from(vec![
+            Op::insert(
+                "Hello",
+                Some(attributes!("bold" => true, "color" => "#e60000")),
+            ),
+            Op::insert("\n", Some(attributes!("header" => 1))),
+            Op::insert(json!({"image": "https://quilljs.com/logo.png"}), None),
+        ]);
+        let (sanitized, stripped) = Sanitizer::quill().sanitize(&delta);
+        assert_eq!(delta, sanitized);
+        assert!(stripped.is_empty());
+    }
+
+    #[test]
+    fn strips_unknown_attributes() {
+        let delta = Delta::from(vec![
+            Op::insert("Hi ", None),
+            Op::insert("there", Some(attributes!("onclick" => "alert(1)"))),
+        ]);
+        let (sanitized, stripped) = Sanitizer::quill().sanitize(&delta);
+        assert_eq!(Delta::from(vec![Op::insert("Hi there", None)]), sanitized);
+        assert_eq!(
+            vec![Stripped::UnknownAttribute {
+                index: 3,
+                key: "onclick".to_string()
+            }],
+            stripped
+        );
+    }
+
+    #[test]
+    fn strips_invalid_values() {
+        let delta = Delta::from(vec![Op::insert(
+            "Text",
+            Some(attributes!("size" => "gigantic", "italic" => true)),
+        )]);
+        let (sanitized, stripped) = Sanitizer::quill().sanitize(&delta);
+        assert_eq!(
+            Delta::from(vec![Op::insert(
+                "Text",
+                Some(attributes!("italic" => true))
+            )]),
+            sanitized
+        );
+        assert_eq!(
+            vec![Stripped::InvalidValue {
+                index: 0,
+                key: "size".to_string(),
+                value: Value::from("gigantic")
+            }],
+            stripped
+        );
+    }
+
+    #[test]
+    fn strips_embeds() {
+        let delta = Delta::from(vec![
+            Op::insert(json!({"video": "https://example.com/v.mp4"}), None),
+            Op::insert(json!({"image": "javascript:alert(1)"}), None),
+            Op::insert(json!(3), None),
+            Op::insert("Text", None),
+        ]);
+        let (sanitized, stripped) = Sanitizer::quill().sanitize(&delta);
+        assert_eq!(Delta::from(vec![Op::insert("Text", None)]), sanitized);
+        assert_eq!(3, stripped.len());
+        assert_eq!(
+            Stripped::Embed {
+                index: 0,
+                value: json!({"video": "https://example.com/v.mp4"})
+            },
+            stripped[0]
+        );
+    }
+
+    #[test]
+    fn sanitizes_embed_attributes() {
+        let delta = Delta::from(vec![
+            Op::insert("A", None),
+            Op::insert_any(
+                json!({"image": "https://quilljs.com/logo.png"}),
+                Some(attributes!("link" => "https://quilljs.com", "width" => "200")),
+            ),
+        ]);
+        let (sanitized, stripped) = Sanitizer::quill().sanitize(&delta);
+        assert_eq!(
+            Delta::from(vec![
+                Op::insert("A", None),
+                Op::insert_any(
+                    json!({"image": "https://quilljs.com/logo.png"}),
+                    Some(attributes!("link" => "https://quilljs.com")),
+                ),
+            ]),
+            sanitized
+        );
+        assert_eq!(
+            vec![Stripped::UnknownAttribute {
+                index: 1,
+                key: "width".to_string()
+            }],
+            stripped
+        );
+    }
+
+    #[test]
+    fn index_skips_deletes() {
+        let delta = Delta::from(vec![
+            Op::retain(2, None),
+            Op::delete(3),
+            Op::insert(json!({"video": "https://example.com/v.mp4"}), None),
+            Op::insert("x", Some(attributes!("onclick" => "alert(1)"))),
+        ]);
+        let (_, stripped) = Sanitizer::quill().sanitize(&delta);
+        assert_eq!(
+            vec![
+                Stripped::Embed {
+                    index: 2,
+                    value: json!({"video": "https://example.com/v.mp4"})
+                },
+                Stripped::UnknownAttribute {
+                    index: 2,
+                    key: "onclick".to_string()
+                },
+            ],
+            stripped
+        );
+    }
+
+    #[test]
+    fn drops_empty_ops() {
+        let delta: Delta = serde_json::from_value(json!({"ops": [
+            {"insert": "hi"},
+            {"retain": 0, "attributes": {"bold": true}},
+            {"delete": 0},
+            {"insert": ""},
+        ]}))
+        .unwrap();
+        assert_eq!(
+            (Delta::new().insert("hi", None), Vec::new()),
+            Sanitizer::quill().sanitize(&delta)
+        );
+    }
+
+    #[test]
+    fn sanitizes_retains() {
+        let delta = Delta::from(vec![
+            Op::retain(2, Some(attributes!("bold" => Value::Null, "x" => 1))),
+            Op::delete(3),
+        ]);
+        let (sanitized, stripped) = Sanitizer::quill().sanitize(&delta);
+        assert_eq!(
+            Delta::from(vec![
+                Op::retain(2, Some(attributes!("bold" => Value::Null))),
+                Op::delete(3),
+            ]),
+            sanitized
+        );
+        assert_eq!(1, stripped.len());
+    }
+
+    #[test]
+    fn custom_rule() {
+        let sanitizer = Sanitizer::new().allow(
+            "mention",
+            Rule::Custom(|value| value.get("id").is_some_and(Value::is_u64)),
+        );
+        let delta = Delta::from(vec![
+            Op::insert("@bob", Some(attributes!("mention" => json!({"id": 1})))),
+            Op::insert("@eve", Some(attributes!("mention" => json!({"id": "1"})))),
+        ]);
+        let (sanitized, stripped) = sanitizer.sanitize(&delta);
+        assert_eq!(
+            Delta::from(vec![
+                Op::insert("@bob", Some(attributes!("mention" => json!({"id": 1})))),
+                Op::insert("@eve", None),
+            ]),
+            sanitized
+        );
+        assert_eq!(1, stripped.len());
+    }
+}
@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+/// Where a registered format may appear in a document
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Scope {
+    /// Attribute of text characters other than newlines, or of embeds
+    Inline,
+    /// Attribute of the newline character ending a line
+    Block,
+    /// Type of an embedded object, i.e. the key of an inserted JSON object
+    Embed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Spec {
+    scope: Scope,
+    values: Option<Vec<Value>>,
+}
+
+/// Registry of formats and embeds a document may contain
+///
+/// Each registered key has a [Scope] and optionally a list of allowed values.
+/// Keys that are not registered are left untouched by [Delta::conform](crate::Delta::conform).
+///
+/// # Example
+///
+/// ```
+/// use quill_delta_rs::{Schema, Scope};
+/// use serde_json::Value;
+///
+/// let schema = Schema::new()
+///     .register("bold", Scope::Inline, Some(vec![Value::from(true)]))
+///     .register("header", Scope::Block, Some(vec![Value::from(1), Value::from(2)]))
+///     .register("image", Scope::Embed, None);
+/// assert_eq!(Some(Scope::Block), schema.scope("header"));
+/// assert!(schema.allows("header", &Value::from(2)));
+/// assert!(!schema.allows("header", &Value::from(3)));
+/// assert!(schema.allows("image", &Value::from("https://quilljs.com/logo.png")));
+/// ```
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Schema {
+    specs: HashMap<String, Spec>,
+}
+
+impl Schema {
+    /// Create an empty [Schema].
+    pub fn new() -> Self {
+        Schema {
+            specs: HashMap::new(),
+        }
+    }
+
+    /// Schema of the formats and embeds shipped with Quill
+    pub fn quill() -> Self {
+        let flag = || Some(vec![Value::Bool(true)]);
+        let strings = |values: &[&str]| Some(values.iter().map(|v| Value::from(*v)).collect());
+        Schema::new()
+            .register("bold", Scope::Inline, flag())
+            .register("italic", Scope::Inline, flag())
+            .register("underline", Scope::Inline, flag())
+            .register("strike", Scope::Inline, flag())
+            .register("code", Scope::Inline, flag())
+            .register("script", Scope::Inline, strings(&["sub", "super"]))
+            .register("link", Scope::Inline, None)
+            .register("color", Scope::Inline, None)
+            .register("background", Scope::Inline, None)
+            .register("font", Scope::Inline, None)
+            .register("size", Scope::Inline, None)
+            .register(
+                "header",
+                Scope::Block,
+                Some((1..=6).map(Value::from).collect()),
+            )
+            .register(
+                "list",
+                Scope::Block,
+                strings(&["ordered", "bullet", "checked", "unchecked"]),
+            )
+            .register(
+                "indent",
+                Scope::Block,
+                Some((1..=8).map(Value::from).collect()),
+            )
+            .register(
+                "align",
+                Scope::Block,
+                strings(&["center", "right", "justify"]),
+            )
+            .register("blockquote", Scope::Block, flag())
+            .register("code-block", Scope::Block, None)
+            .register("direction", Scope::Block, strings(&["rtl"]))
+            .register("image", Scope::Embed, None)
+            .register("video", Scope::Embed, None)
+            .register("formula", Scope::Embed, None)
+    }
+
+    /// Register a format or embed.
+    ///
+    /// If `values` is [None], any value is allowed. Registering an existing key replaces it.
+    pub fn register<K: Into<String>>(
+        mut self,
+        key: K,
+        scope: Scope,
+        values: Option<Vec<Value>>,
+    ) -> Self {
+        self.specs.insert(key.into(), Spec { scope, values });
+        self
+    }
+
+    /// Get the [Scope] of a registered key
+    pub fn scope(&self, key: &str) -> Option<Scope> {
+        self.specs.get(key).map(|spec| spec.scope)
+    }
+
+    /// Whether the key is registered
+    pub fn contains(&self, key: &str) -> bool {
+        self.specs.contains_key(key)
+    }
+
+    /// Whether `value` is allowed for the key.
+    ///
+    /// [Value::Null], which removes a format, is always allowed. Unregistered keys allow
+    /// any value.
+    pub fn allows(&self, key: &str, value: &Value) -> bool {
+        if value.is_null() {
+            return true;
+        }
+        match self.specs.get(key) {
+            Some(Spec {
+                values: Some(values),
+                ..
+            }) => values.contains(value),
+            _ => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Value, json};
+
+    use super::{Schema, Scope};
+
+    #[test]
+    fn register() {
+        let schema = Schema::new().register("mention", Scope::Inline, None);
+        assert!(schema.contains("mention"));
+        assert_eq!(Some(Scope::Inline), schema.scope("mention"));
+        assert!(!schema.contains("bold"));
+        assert_eq!(None, schema.scope("bold"));
+    }
+
+    #[test]
+    fn register_replaces() {
+        let schema = Schema::new()
+            .register("mention", Scope::Inline, None)
+            .register("mention", Scope::Embed, None);
+        assert_eq!(Some(Scope::Embed), schema.scope("mention"));
+    }
+
+    #[test]
+    fn allows() {
+        let schema = Schema::new().register("header", Scope::Block, Some(vec![json!(1)]));
+        assert!(schema.allows("header", &json!(1)));
+        assert!(!schema.allows("header", &json!(2)));
+        assert!(schema.allows("header", &Value::Null));
+        assert!(schema.allows("unknown", &json!("anything")));
+    }
+
+    #[test]
+    fn quill() {
+        let schema = Schema::quill();
+        assert_eq!(Some(Scope::Inline), schema.scope("bold"));
+        assert_eq!(Some(Scope::Block), schema.scope("code-block"));
+        assert_eq!(Some(Scope::Embed), schema.scope("image"));
+        assert!(schema.allows("header", &json!(6)));
+        assert!(!schema.allows("header", &json!(7)));
+        assert!(!schema.allows("bold", &json!(false)));
+        assert!(schema.allows("code-block", &json!("rust")));
+    }
+}
@@ -0,0 +1,577 @@
+use std::cmp::min;
+
+use regex::{Regex, RegexBuilder};
+use serde_json::Value;
+
+use crate::{
+    attributes::AttributesMap,
+    delta::Delta,
+    op::{Op, OpType},
+    range::Range,
+};
+
+// Stand-in for embeds in the searched text, so that an embed is a single opaque character
+const EMBED: char = '\u{fffc}';
+
+/// Pattern to search for in a document [Delta]
+///
+/// # Example
+///
+/// ```
+/// use quill_delta_rs::{Delta, Op, Query, Range, {attributes, AttributesMap}};
+///
+/// let doc = Delta::from(vec![
+///     Op::insert("Quill ", None),
+///     Op::insert("quill", Some(attributes!("bold" => true))),
+///     Op::insert("\n", None),
+/// ]);
+/// assert_eq!(vec![Range::new(6, 5)], doc.find(&Query::new("quill")));
+/// assert_eq!(
+///     vec![Range::new(0, 5), Range::new(6, 5)],
+///     doc.find(&Query::new("quill").case_insensitive(true))
+/// );
+/// assert_eq!(
+///     vec![Range::new(6, 5)],
+///     doc.find(&Query::regex("[Qq]uill").unwrap().with_format(attributes!("bold" => true)))
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct Query {
+    pattern: String,
+    is_regex: bool,
+    format: Option<AttributesMap>,
+    regex: Regex,
+}
+
+impl Query {
+    /// Query matching `text` literally
+    pub fn new(text: &str) -> Self {
+        let pattern = regex::escape(text);
+        Query {
+            regex: Regex::new(&pattern).unwrap(),
+            pattern,
+            is_regex: false,
+            format: None,
+        }
+    }
+
+    /// Query matching a regular expression
+    ///
+    /// Embeds are matched by any character class including U+FFFC, such as `.`.
+    /// Replacements of a regular expression query can refer to its capture groups,
+    /// e.g. `$1`.
+    pub fn regex(pattern: &str) -> Result<Self, regex::Error> {
+        Ok(Query {
+            regex: Regex::new(pattern)?,
+            pattern: pattern.to_string(),
+            is_regex: true,
+            format: None,
+        })
+    }
+
+    /// Whether case is ignored when matching
+    pub fn case_insensitive(mut self, yes: bool) -> Self {
+        self.regex = RegexBuilder::new(&self.pattern)
+            .case_insensitive(yes)
+            .build()
+            .unwrap();
+        self
+    }
+
+    /// Only match text having all the given formats
+    pub fn with_format(mut self, format: AttributesMap) -> Self {
+        self.format = Some(format);
+        self
+    }
+}
+
+/// Text of a document [Delta] to run a [Query] on
+struct Haystack<'a> {
+    text: String,
+    // (index in delta, op) of every insert
+    ops: Vec<(usize, &'a Op)>,
+    // index in delta of every newline
+    newlines: Vec<usize>,
+}
+
+impl<'a> Haystack<'a> {
+    fn new(delta: &'a Delta) -> Self {
+        let mut haystack = Haystack {
+            text: String::new(),
+            ops: Vec::new(),
+            newlines: Vec::new(),
+        };
+        let mut index = 0;
+        for op in delta.ops() {
+            match op.kind() {
+                OpType::Insert(Value::String(text)) => {
+                    haystack.text.push_str(text);
+                    haystack.newlines.extend(
+                        text.chars()
+                            .enumerate()
+                            .filter(|(_, c)| *c == '\n')
+                            .map(|(i, _)| index + i),
+                    );
+                }
+                OpType::Insert(_) => haystack.text.push(EMBED),
+                _ => continue,
+            }
+            haystack.ops.push((index, op));
+            index += op.len();
+        }
+        haystack
+    }
+
+    /// Converter of increasing byte positions in the text, as regex matches have, to indexes
+    /// in the [Delta], which count characters
+    fn indexer(&self) -> impl FnMut(usize) -> usize + '_ {
+        let mut position = 0;
+        let mut index = 0;
+        move |to| {
+            index += self.text[position..to].chars().count();
+            position = to;
+            index
+        }
+    }
+
+    /// Slice of the [Delta] from `index`
+    fn slice(&self, index: usize, length: usize) -> Delta {
+        let first = self
+            .ops
+            .partition_point(|(start, op)| start + op.len() <= index);
+        let Some((start, _)) = self.ops.get(first) else {
+            return Delta::new();
+        };
+        let ops = self.ops[first..]
+            .iter()
+            .take_while(|(start, _)| *start < index + length)
+            .map(|(_, op)| (*op).clone())
+            .collect::<Vec<_>>();
+        Delta::from(ops).slice(index - start, Some(index + length - start))
+    }
+
+    /// Index of the first newline at or after `index`
+    fn newline(&self, index: usize) -> Option<usize> {
+        let newline = self.newlines.partition_point(|newline| *newline < index);
+        self.newlines.get(newline).copied()
+    }
+
+    /// Attributes of the insert at `index`
+    fn attributes(&self, index: usize) -> Option<&AttributesMap> {
+        let op = self.ops.partition_point(|(start, _)| *start <= index);
+        self.ops[op - 1].1.attributes()
+    }
+
+    /// Whether every insert in the range has all the attributes of `format`
+    fn has_format(&self, index: usize, length: usize, format: &AttributesMap) -> bool {
+        let first = self
+            .ops
+            .partition_point(|(start, op)| start + op.len() <= index);
+        self.ops[first..]
+            .iter()
+            .take_while(|(start, _)| *start < index + length)
+            .all(|(_, op)| {
+                format
+                    .into_iter()
+                    .all(|(key, value)| op.attributes().and_then(|a| a.get(&key)) == Some(&value))
+            })
+    }
+
+    /// Attributes of the newline ending the line containing `index`
+    fn line_attributes(&self, index: usize) -> Option<&AttributesMap> {
+        self.newline(index)
+            .and_then(|newline| self.attributes(newline))
+    }
+
+    /// Push the retain giving `line` formats to the newline ending a line joined by a
+    /// replacement
+    fn join_line(
+        &self,
+        change: &mut Delta,
+        change_len: &mut usize,
+        newline: usize,
+        line: AttributesMap,
+    ) {
+        let attributes = self.attributes(newline).cloned().unwrap_or_default();
+        if let Some(attributes) = AttributesMap::diff(attributes, line) {
+            if newline > *change_len {
+                change.push(Op::retain(newline - *change_len, None));
+            }
+            change.push(Op::retain(1, Some(attributes)));
+            *change_len = newline + 1;
+        }
+    }
+}
+
+impl Delta {
+    /// Find the [Range]s of a document [Delta] matching a [Query]
+    ///
+    /// Embeds count as a single U+FFFC character. Empty matches are ignored.
+    pub fn find(&self, query: &Query) -> Vec<Range> {
+        let haystack = Haystack::new(self);
+        let mut index_of = haystack.indexer();
+        query
+            .regex
+            .find_iter(&haystack.text)
+            .filter(|m| !m.is_empty())
+            .map(|m| {
+                let index = index_of(m.start());
+                Range::new(index, index_of(m.end()) - index)
+            })
+            .filter(|range| match &query.format {
+                Some(format) => haystack.has_format(range.index, range.length, format),
+                None => true,
+            })
+            .collect()
+    }
+
+    /// Build the change replacing every match of a [Query] in a document [Delta]
+    ///
+    /// Each replacement takes the formats of its match as in [Delta::replace_range]: each
+    /// character takes the inline formats of the matched character at the same position, and
+    /// newlines of the replacement, as well as lines joined by a match spanning newlines,
+    /// carry the line formats of the line the match starts on.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use quill_delta_rs::{Delta, Op, Query, {attributes, AttributesMap}};
+    ///
+    /// let doc = Delta::from(vec![
+    ///     Op::insert("cat", Some(attributes!("bold" => true))),
+    ///     Op::insert(" and ", None),
+    ///     Op::insert("cat", Some(attributes!("italic" => true))),
+    ///     Op::insert("\n", None),
+    /// ]);
+    /// let change = doc.replace_all(&Query::new("cat"), "dog");
+    /// assert_eq!(
+    ///     Delta::from(vec![
+    ///         Op::insert("dog", Some(attributes!("bold" => true))),
+    ///         Op::insert(" and ", None),
+    ///         Op::insert("dog", Some(attributes!("italic" => true))),
+    ///         Op::insert("\n", None),
+    ///     ]),
+    ///     doc.compose(&change)
+    /// );
+    /// ```
+    pub fn replace_all(&self, query: &Query, replacement: &str) -> Delta {
+        let haystack = Haystack::new(self);
+        let mut index_of = haystack.indexer();
+        let mut change = Delta::new();
+        let mut change_len = 0;
+        // Newline ending a line joined by a replacement, and the line formats it takes
+        let mut joined: Option<(usize, AttributesMap)> = None;
+        for captures in query.regex.captures_iter(&haystack.text) {
+            let m = captures.get(0).unwrap();
+            if m.is_empty() {
+                continue;
+            }
+            let index = index_of(m.start());
+            let length = index_of(m.end()) - index;
+            if let Some(format) = &query.format
+                && !haystack.has_format(index, length, format)
+            {
+                continue;
+            }
+
+            let mut text = String::new();
+            if query.is_regex {
+                captures.expand(replacement, &mut text);
+            } else {
+                text.push_str(replacement);
+            }
+            if let Some((newline, line)) = joined.take_if(|(newline, _)| *newline < index) {
+                haystack.join_line(&mut change, &mut change_len, newline, line);
+            }
+            let line = match &joined {
+                Some((_, line)) => line.clone(),
+                None => haystack.line_attributes(index).cloned().unwrap_or_default(),
+            };
+            let mut formats = haystack.slice(index, length).inline_formats();
+            if formats.is_empty() {
+                let before = haystack.slice(index.saturating_sub(1), min(index, 1));
+                formats = before.inline_formats();
+            }
+            if index > change_len {
+                change.push(Op::retain(index - change_len, None));
+            }
+            change.push_formatted(&text, &formats, line.clone());
+            change.push(Op::delete(length));
+            change_len = index + length;
+            if haystack
+                .newline(index)
+                .is_some_and(|newline| newline < index + length)
+            {
+                joined = haystack
+                    .newline(index + length)
+                    .map(|newline| (newline, line));
+            }
+        }
+        if let Some((newline, line)) = joined {
+            haystack.join_line(&mut change, &mut change_len, newline, line);
+        }
+        change
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{AttributesMap, Delta, Op, Range};
+
+    use super::Query;
+
+    fn document() -> Delta {
+        Delta::from(vec![
+            Op::insert("The Cat", Some(attributes!("bold" => true))),
+            Op::insert("\n", Some(attributes!("header" => 1))),
+            Op::insert("A cat", None),
+            Op::insert(json!({"image": "cat.png"}), None),
+            Op::insert("cat and the cat\n", None),
+        ])
+    }
+
+    #[test]
+    fn find_text() {
+        assert_eq!(
+            vec![Range::new(10, 3), Range::new(14, 3), Range::new(26, 3)],
+            document().find(&Query::new("cat"))
+        );
+    }
+
+    #[test]
+    fn find_case_insensitive() {
+        assert_eq!(
+            vec![
+                Range::new(4, 3),
+                Range::new(10, 3),
+                Range::new(14, 3),
+                Range::new(26, 3)
+            ],
+            document().find(&Query::new("CAT").case_insensitive(true))
+        );
+    }
+
+    #[test]
+    fn find_escapes_literal() {
+        let doc = Delta::from(vec![Op::insert("1+1=2 11=2\n", None)]);
+        assert_eq!(vec![Range::new(0, 5)], doc.find(&Query::new("1+1=2")));
+    }
+
+    #[test]
+    fn find_regex() {
+        let query = Query::regex(r"\bcat\b").unwrap();
+        assert_eq!(
+            vec![Range::new(10, 3), Range::new(14, 3), Range::new(26, 3)],
+            document().find(&query)
+        );
+        let query = Query::regex(r"\bcat and").unwrap();
+        assert_eq!(vec![Range::new(14, 7)], document().find(&query));
+    }
+
+    #[test]
+    fn embeds_are_opaque() {
+        // Embeds are not newlines
+        let query = Query::regex("(?m)cat$").unwrap();
+        assert_eq!(vec![Range::new(26, 3)], document().find(&query));
+        // Embeds are single characters
+        let query = Query::regex("cat.cat").unwrap();
+        assert_eq!(vec![Range::new(10, 7)], document().find(&query));
+        let query = Query::new("\u{fffc}");
+        assert_eq!(vec![Range::new(13, 1)], document().find(&query));
+    }
+
+    #[test]
+    fn find_with_format() {
+        let query = Query::new("cat")
+            .case_insensitive(true)
+            .with_format(attributes!("bold" => true));
+        assert_eq!(vec![Range::new(4, 3)], document().find(&query));
+    }
+
+    #[test]
+    fn find_ignores_empty_matches() {
+        let query = Query::regex("x*").unwrap();
+        assert!(document().find(&query).is_empty());
+    }
+
+    #[test]
+    fn invalid_regex() {
+        assert!(Query::regex("(cat").is_err());
+    }
+
+    #[test]
+    fn replace_all() {
+        let doc = document();
+        let change = doc.replace_all(&Query::new("cat").case_insensitive(true), "dog");
+        assert_eq!(
+            Delta::from(vec![
+                Op::insert("The dog", Some(attributes!("bold" => true))),
+                Op::insert("\n", Some(attributes!("header" => 1))),
+                Op::insert("A dog", None),
+                Op::insert(json!({"image": "cat.png"}), None),
+                Op::insert("dog and the dog\n", None),
+            ]),
+            doc.compose(&change)
+        );
+    }
+
+    #[test]
+    fn replace_all_is_single_change() {
+        let doc = document();
+        let change = doc.replace_all(&Query::new("cat"), "dog");
+        assert_eq!(
+            Delta::new()
+                .retain(10, None)
+                .insert("dog", None)
+                .delete(3)
+                .retain(1, None)
+                .insert("dog", None)
+                .delete(3)
+                .retain(9, None)
+                .insert("dog", None)
+                .delete(3),
+            change
+        );
+    }
+
+    #[test]
+    fn replace_all_with_captures() {
+        let doc = Delta::from(vec![Op::insert("2024-01-31\n", None)]);
+        let query = Query::regex(r"(\d+)-(\d+)-(\d+)").unwrap();
+        let change = doc.replace_all(&query, "$3/$2/$1");
+        assert_eq!(
+            Delta::from(vec![Op::insert("31/01/2024\n", None)]),
+            doc.compose(&change)
+        );
+    }
+
+    #[test]
+    fn replace_all_literal_dollar() {
+        let doc = Delta::from(vec![Op::insert("price\n", None)]);
+        let change = doc.replace_all(&Query::new("price"), "$1");
+        assert_eq!(
+            Delta::from(vec![Op::insert("$1\n", None)]),
+            doc.compose(&change)
+        );
+    }
+
+    #[test]
+    fn replace_all_with_newlines() {
+        let doc = document();
+        let change = doc.replace_all(&Query::new("Cat"), "Cat\nTitle");
+        assert_eq!(
+            Delta::from(vec![
+                Op::insert("The Cat", Some(attributes!("bold" => true))),
+                Op::insert("\n", Some(attributes!("header" => 1))),
+                Op::insert("Title", Some(attributes!("bold" => true))),
+                Op::insert("\n", Some(attributes!("header" => 1))),
+                Op::insert("A cat", None),
+                Op::insert(json!({"image": "cat.png"}), None),
+                Op::insert("cat and the cat\n", None),
+            ]),
+            doc.compose(&change)
+        );
+    }
+
+    #[test]
+    fn find_after_non_ascii() {
+        let doc = Delta::from(vec![
+            Op::insert("héllo ", None),
+            Op::insert(json!({"image": "cat.png"}), None),
+            Op::insert(" 猫 cat\n", None),
+        ]);
+        assert_eq!(vec![Range::new(10, 3)], doc.find(&Query::new("cat")));
+        assert_eq!(vec![Range::new(8, 1)], doc.find(&Query::new("猫")));
+        assert_eq!(vec![Range::new(1, 1)], doc.find(&Query::new("é")));
+    }
+
+    #[test]
+    fn replace_all_after_non_ascii() {
+        let doc = Delta::from(vec![Op::insert("héllo cat\n", None)]);
+        let change = doc.replace_all(&Query::new("cat"), "dog");
+        assert_eq!(
+            Delta::new().retain(6, None).insert("dog", None).delete(3),
+            change
+        );
+        assert_eq!(
+            Delta::from(vec![Op::insert("héllo dog\n", None)]),
+            doc.compose(&change)
+        );
+        let change = doc.replace_all(&Query::new("é"), "ë");
+        assert_eq!(
+            Delta::from(vec![Op::insert("hëllo cat\n", None)]),
+            doc.compose(&change)
+        );
+    }
+
+    #[test]
+    fn replace_all_mixed_formats() {
+        let doc = document();
+        let change = doc.replace_all(&Query::new("The Cat"), "Une Chatte");
+        assert_eq!(
+            Delta::from(vec![
+                Op::insert("Une Chatte", Some(attributes!("bold" => true))),
+                Op::insert("\n", Some(attributes!("header" => 1))),
+                Op::insert("A cat", None),
+                Op::insert(json!({"image": "cat.png"}), None),
+                Op::insert("cat and the cat\n", None),
+            ]),
+            doc.compose(&change)
+        );
+        let doc = Delta::from(vec![
+            Op::insert("bold", Some(attributes!("bold" => true))),
+            Op::insert("plain\n", None),
+        ]);
+        let change = doc.replace_all(&Query::new("ldpl"), "LDPL");
+        assert_eq!(
+            Delta::from(vec![
+                Op::insert("boLD", Some(attributes!("bold" => true))),
+                Op::insert("PLain\n", None),
+            ]),
+            doc.compose(&change)
+        );
+    }
+
+    #[test]
+    fn replace_all_across_lines() {
+        let doc = Delta::from(vec![
+            Op::insert("Title", None),
+            Op::insert("\n", Some(attributes!("header" => 1))),
+            Op::insert("Body\nMore\nEnd\n", None),
+        ]);
+        let change = doc.replace_all(&Query::new("\nBody"), "X");
+        assert_eq!(
+            Delta::from(vec![
+                Op::insert("TitleX", None),
+                Op::insert("\n", Some(attributes!("header" => 1))),
+                Op::insert("More\nEnd\n", None),
+            ]),
+            doc.compose(&change)
+        );
+        // Consecutive matches join the lines into the first one
+        let change = doc.replace_all(&Query::regex("\n[A-Z]").unwrap(), "");
+        assert_eq!(
+            Delta::from(vec![
+                Op::insert("Titleodyorend", None),
+                Op::insert("\n", Some(attributes!("header" => 1))),
+            ]),
+            doc.compose(&change)
+        );
+    }
+
+    #[test]
+    fn replace_all_embed() {
+        let doc = document();
+        let change = doc.replace_all(&Query::new("\u{fffc}"), " ");
+        assert_eq!(
+            Delta::from(vec![
+                Op::insert("The Cat", Some(attributes!("bold" => true))),
+                Op::insert("\n", Some(attributes!("header" => 1))),
+                Op::insert("A cat cat and the cat\n", None),
+            ]),
+            doc.compose(&change)
+        );
+    }
+}
@@ -0,0 +1,225 @@
+use std::fmt;
+
+use crate::{delta::Delta, op::Op};
+
+/// An error rejecting a change submitted to a [Server]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServerError {
+    /// The change is based on a revision the server doesn't have yet
+    UnknownRevision { revision: usize, current: usize },
+    /// Once rebased, the change retains or deletes past the end of the document
+    InvalidChange { length: usize, document: usize },
+}
+
+impl fmt::Display for ServerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServerError::UnknownRevision { revision, current } => write!(
+                f,
+                "change is based on revision {revision} but the current revision is {current}"
+            ),
+            ServerError::InvalidChange { length, document } => write!(
+                f,
+                "change spans {length} characters but the document has {document}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ServerError {}
+
+/// Server side of operational transformation
+///
+/// Holds the current document and the append-only log of the changes applied to it.
+/// Revision `n` is the document once the first `n` changes of the log are applied. A client
+/// submits a change along with the revision it was made on; the server transforms it
+/// against the changes applied since, applies it and returns it to be broadcast to every
+/// client, the author included as an acknowledgement.
+///
+/// # Example
+///
+/// ```
+/// use quill_delta_rs::{Delta, Server};
+///
+/// let mut server = Server::new(Delta::new().insert("Hello\n", None));
+///
+/// // 2 clients edit revision 0 concurrently
+/// server.receive(0, Delta::new().insert("Oh ", None)).unwrap();
+/// let second = server.receive(0, Delta::new().retain(5, None).insert("!", None)).unwrap();
+///
+/// assert_eq!(Delta::new().retain(8, None).insert("!", None), second);
+/// assert_eq!(2, server.revision());
+/// assert_eq!(&Delta::new().insert("Oh Hello!\n", None), server.document());
+/// ```
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Server {
+    document: Delta,
+    revisions: Vec<Delta>,
+}
+
+impl Server {
+    /// Create a [Server] with a document at revision 0
+    pub fn new(document: Delta) -> Self {
+        Server {
+            document,
+            revisions: Vec::new(),
+        }
+    }
+
+    /// The current document
+    pub fn document(&self) -> &Delta {
+        &self.document
+    }
+
+    /// The current revision, i.e. the number of changes applied
+    pub fn revision(&self) -> usize {
+        self.revisions.len()
+    }
+
+    /// The changes applied since `revision`, to bring a client up to date
+    ///
+    /// Returns [None] if the revision is unknown.
+    pub fn revisions_since(&self, revision: usize) -> Option<&[Delta]> {
+        self.revisions.get(revision..)
+    }
+
+    /// Apply a change made on `revision` of the document
+    ///
+    /// Returns the change rebased on the current revision, as applied to the document.
+    pub fn receive(&mut self, revision: usize, change: Delta) -> Result<Delta, ServerError> {
+        let concurrent = self
+            .revisions_since(revision)
+            .ok_or(ServerError::UnknownRevision {
+                revision,
+                current: self.revision(),
+            })?;
+        let change = concurrent
+            .iter()
+            .fold(change, |change, applied| applied.transform(&change, true));
+
+        let length = change
+            .ops()
+            .iter()
+            .filter(|op| !op.is_insert())
+            .map(Op::len)
+            .sum();
+        let document = self.document.len();
+        if length > document {
+            return Err(ServerError::InvalidChange { length, document });
+        }
+        self.document = self.document.compose(&change);
+        self.revisions.push(change.clone());
+        Ok(change)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{AttributesMap, Delta, Op};
+
+    use super::{Server, ServerError};
+
+    fn server() -> Server {
+        Server::new(Delta::new().insert("The fox\n", None))
+    }
+
+    #[test]
+    fn receive_current_revision() {
+        let mut server = server();
+        let change = Delta::new().retain(4, None).insert("red ", None);
+        assert_eq!(Ok(change.clone()), server.receive(0, change.clone()));
+        assert_eq!(1, server.revision());
+        assert_eq!(Some(&[change][..]), server.revisions_since(0));
+        assert_eq!(
+            &Delta::new().insert("The red fox\n", None),
+            server.document()
+        );
+    }
+
+    #[test]
+    fn receive_concurrent() {
+        let mut server = server();
+        server
+            .receive(0, Delta::new().retain(4, None).insert("red ", None))
+            .unwrap();
+        server.receive(1, Delta::new().delete(4)).unwrap();
+        let change = server
+            .receive(0, Delta::new().retain(4, None).insert("quick ", None))
+            .unwrap();
+        // Inserts already applied at the same index go first
+        assert_eq!(Delta::new().retain(4, None).insert("quick ", None), change);
+        assert_eq!(
+            &Delta::new().insert("red quick fox\n", None),
+            server.document()
+        );
+    }
+
+    #[test]
+    fn receive_concurrent_delete() {
+        let mut server = server();
+        server
+            .receive(0, Delta::new().retain(4, None).delete(3))
+            .unwrap();
+        let change = server
+            .receive(0, Delta::new().retain(5, None).insert("o", None))
+            .unwrap();
+        assert_eq!(Delta::new().retain(4, None).insert("o", None), change);
+        assert_eq!(&Delta::new().insert("The o\n", None), server.document());
+    }
+
+    #[test]
+    fn receive_format_embed() {
+        let mut server = Server::new(Delta::from(vec![
+            Op::insert("A", None),
+            Op::insert(json!({"image": "fox.png"}), None),
+            Op::insert("B\n", None),
+        ]));
+        server
+            .receive(0, Delta::new().retain(3, Some(attributes!("bold" => true))))
+            .unwrap();
+        server
+            .receive(
+                1,
+                Delta::new()
+                    .retain(1, None)
+                    .retain(1, Some(attributes!("width" => "200"))),
+            )
+            .unwrap();
+        let expected: Delta = serde_json::from_value(json!([
+            {"insert": "A", "attributes": {"bold": true}},
+            {"insert": {"image": "fox.png"}, "attributes": {"bold": true, "width": "200"}},
+            {"insert": "B", "attributes": {"bold": true}},
+            {"insert": "\n"}
+        ]))
+        .unwrap();
+        assert_eq!(&expected, server.document());
+    }
+
+    #[test]
+    fn unknown_revision() {
+        let mut server = server();
+        assert_eq!(
+            Err(ServerError::UnknownRevision {
+                revision: 1,
+                current: 0
+            }),
+            server.receive(1, Delta::new().insert("A", None))
+        );
+        assert_eq!(None, server.revisions_since(1));
+    }
+
+    #[test]
+    fn invalid_change() {
+        let mut server = server();
+        assert_eq!(
+            Err(ServerError::InvalidChange {
+                length: 10,
+                document: 8
+            }),
+            server.receive(0, Delta::new().retain(9, None).delete(1))
+        );
+        assert_eq!(0, server.revision());
+    }
+}
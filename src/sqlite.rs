@@ -0,0 +1,264 @@
+use std::path::Path;
+
+use rusqlite::{Connection, OptionalExtension, TransactionBehavior, params};
+
+use crate::{
+    delta::Delta,
+    storage::{Storage, StorageError},
+};
+
+impl From<rusqlite::Error> for StorageError {
+    fn from(error: rusqlite::Error) -> Self {
+        StorageError::Sqlite(error)
+    }
+}
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS snapshots (
+    document TEXT PRIMARY KEY,
+    revision INTEGER NOT NULL,
+    delta TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS changes (
+    document TEXT NOT NULL,
+    revision INTEGER NOT NULL,
+    delta TEXT NOT NULL,
+    PRIMARY KEY (document, revision)
+);
+";
+
+/// A [Storage] in a SQLite database, available with the `sqlite` feature
+///
+/// Snapshots and changes are stored in the `snapshots` and `changes` tables, created if
+/// missing, as the JSON serialization of their [Delta]. A change is stored with the
+/// revision it brings the document to.
+pub struct SqliteStorage {
+    connection: Connection,
+}
+
+impl SqliteStorage {
+    /// Open or create the database at `path`
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, StorageError> {
+        SqliteStorage::from_connection(Connection::open(path)?)
+    }
+
+    /// Open a database in memory
+    pub fn open_in_memory() -> Result<Self, StorageError> {
+        SqliteStorage::from_connection(Connection::open_in_memory()?)
+    }
+
+    /// Use an open connection
+    pub fn from_connection(connection: Connection) -> Result<Self, StorageError> {
+        connection.execute_batch(SCHEMA)?;
+        Ok(SqliteStorage { connection })
+    }
+}
+
+fn snapshot_revision(connection: &Connection, document: &str) -> Result<usize, StorageError> {
+    let revision = connection
+        .query_row(
+            "SELECT revision FROM snapshots WHERE document = ?1",
+            params![document],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(revision.unwrap_or_default())
+}
+
+fn revision(connection: &Connection, document: &str) -> Result<usize, StorageError> {
+    let revision: Option<usize> = connection.query_row(
+        "SELECT MAX(revision) FROM changes WHERE document = ?1",
+        params![document],
+        |row| row.get(0),
+    )?;
+    match revision {
+        Some(revision) => Ok(revision),
+        None => snapshot_revision(connection, document),
+    }
+}
+
+fn load_snapshot(connection: &Connection, document: &str) -> Result<(usize, Delta), StorageError> {
+    let snapshot: Option<(usize, String)> = connection
+        .query_row(
+            "SELECT revision, delta FROM snapshots WHERE document = ?1",
+            params![document],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+    match snapshot {
+        Some((revision, delta)) => Ok((revision, serde_json::from_str(&delta)?)),
+        None => Ok((0, Delta::new())),
+    }
+}
+
+fn changes_since(
+    connection: &Connection,
+    document: &str,
+    revision: usize,
+) -> Result<Vec<Delta>, StorageError> {
+    let snapshot = snapshot_revision(connection, document)?;
+    if revision < snapshot {
+        return Err(StorageError::Compacted { revision, snapshot });
+    }
+    let current = self::revision(connection, document)?;
+    if revision > current {
+        return Err(StorageError::UnknownRevision { revision, current });
+    }
+    let mut statement = connection.prepare(
+        "SELECT delta FROM changes WHERE document = ?1 AND revision > ?2 ORDER BY revision",
+    )?;
+    let rows = statement.query_map(params![document, revision], |row| row.get::<_, String>(0))?;
+    let mut changes = Vec::new();
+    for delta in rows {
+        changes.push(serde_json::from_str(&delta?)?);
+    }
+    Ok(changes)
+}
+
+impl Storage for SqliteStorage {
+    fn load_snapshot(&self, document: &str) -> Result<(usize, Delta), StorageError> {
+        load_snapshot(&self.connection, document)
+    }
+
+    /// Runs in an immediate transaction, so that connections appending to the same document
+    /// concurrently get consecutive revisions.
+    fn append_change(&mut self, document: &str, change: &Delta) -> Result<usize, StorageError> {
+        let transaction = self
+            .connection
+            .transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let revision = revision(&transaction, document)? + 1;
+        transaction.execute(
+            "INSERT INTO changes (document, revision, delta) VALUES (?1, ?2, ?3)",
+            params![document, revision, serde_json::to_string(change)?],
+        )?;
+        transaction.commit()?;
+        Ok(revision)
+    }
+
+    fn changes_since(&self, document: &str, revision: usize) -> Result<Vec<Delta>, StorageError> {
+        changes_since(&self.connection, document, revision)
+    }
+
+    /// Replace the snapshot by the current document and delete the changes it includes
+    ///
+    /// Runs in a single immediate transaction, so changes appended concurrently through
+    /// another connection are either included in the snapshot or kept.
+    fn compact(&mut self, document: &str) -> Result<(), StorageError> {
+        let transaction = self
+            .connection
+            .transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let (revision, snapshot) = load_snapshot(&transaction, document)?;
+        let changes = changes_since(&transaction, document, revision)?;
+        let revision = revision + changes.len();
+        let delta = changes
+            .iter()
+            .fold(snapshot, |delta, change| delta.compose(change));
+        transaction.execute(
+            "INSERT OR REPLACE INTO snapshots (document, revision, delta) VALUES (?1, ?2, ?3)",
+            params![document, revision, serde_json::to_string(&delta)?],
+        )?;
+        transaction.execute(
+            "DELETE FROM changes WHERE document = ?1 AND revision <= ?2",
+            params![document, revision],
+        )?;
+        transaction.commit()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, thread};
+
+    use crate::{Delta, Storage, StorageError};
+
+    use super::SqliteStorage;
+
+    fn storage() -> SqliteStorage {
+        let mut storage = SqliteStorage::open_in_memory().unwrap();
+        for change in [
+            Delta::new().insert("Hello\n", None),
+            Delta::new().retain(5, None).insert(" World", None),
+            Delta::new().delete(1).insert("J", None),
+        ] {
+            storage.append_change("doc", &change).unwrap();
+        }
+        storage
+    }
+
+    #[test]
+    fn unknown_document() {
+        let storage = SqliteStorage::open_in_memory().unwrap();
+        assert_eq!((0, Delta::new()), storage.load_document("doc").unwrap());
+    }
+
+    #[test]
+    fn changes_since() {
+        let storage = storage();
+        assert_eq!(
+            vec![Delta::new().delete(1).insert("J", None)],
+            storage.changes_since("doc", 2).unwrap()
+        );
+        assert!(matches!(
+            storage.changes_since("doc", 4),
+            Err(StorageError::UnknownRevision {
+                revision: 4,
+                current: 3
+            })
+        ));
+    }
+
+    #[test]
+    fn compact() {
+        let mut storage = storage();
+        let document = storage.load_document("doc").unwrap();
+        assert_eq!((3, Delta::new().insert("Jello World\n", None)), document);
+        storage.compact("doc").unwrap();
+        assert_eq!(document, storage.load_snapshot("doc").unwrap());
+        assert!(matches!(
+            storage.changes_since("doc", 1),
+            Err(StorageError::Compacted {
+                revision: 1,
+                snapshot: 3
+            })
+        ));
+        assert_eq!(
+            4,
+            storage
+                .append_change("doc", &Delta::new().retain(11, None).insert("!", None))
+                .unwrap()
+        );
+        assert_eq!(
+            (4, Delta::new().insert("Jello World!\n", None)),
+            storage.load_document("doc").unwrap()
+        );
+    }
+
+    #[test]
+    fn concurrent_appends() {
+        let path = std::env::temp_dir().join(format!("quill-delta-{}.db", std::process::id()));
+        let appends: Vec<_> = (0..2)
+            .map(|_| {
+                let mut storage = SqliteStorage::open(&path).unwrap();
+                thread::spawn(move || {
+                    (0..20)
+                        .map(|_| {
+                            storage
+                                .append_change("doc", &Delta::new().insert("a", None))
+                                .unwrap()
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        let mut revisions: Vec<usize> = appends
+            .into_iter()
+            .flat_map(|append| append.join().unwrap())
+            .collect();
+        revisions.sort();
+        assert_eq!((1..=40).collect::<Vec<_>>(), revisions);
+        let storage = SqliteStorage::open(&path).unwrap();
+        assert_eq!(40, storage.load_document("doc").unwrap().1.len());
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,247 @@
+use std::{
+    collections::HashSet,
+    fmt,
+    time::{Duration, SystemTime},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::delta::Delta;
+
+/// A change of a document log, along with who made it and when
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Change {
+    pub delta: Delta,
+    pub author: String,
+    pub time: SystemTime,
+    /// The revision of the document once the change is applied
+    pub revision: usize,
+}
+
+/// An error of a squashed change log
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SquashError {
+    /// The squashed log doesn't reproduce the document of the original log at `revision`
+    Diverged { revision: usize },
+}
+
+impl fmt::Display for SquashError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SquashError::Diverged { revision } => write!(
+                f,
+                "squashed log doesn't reproduce the document at revision {revision}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SquashError {}
+
+/// Squash a log of `changes` made to the document `base`
+///
+/// Consecutive changes by the same author are composed into a single change, keeping the
+/// time and revision of the last one, as long as they are made within `window` of the first
+/// one. The `checkpoints` revisions are never squashed over: the squashed log still has a
+/// change ending at each of them. The squashed log is checked with [verify] before being
+/// returned.
+///
+/// # Example
+///
+/// ```
+/// use std::time::{Duration, SystemTime};
+/// use quill_delta_rs::{squash, Change, Delta};
+///
+/// let time = SystemTime::UNIX_EPOCH;
+/// let change = |delta: Delta, revision| Change {
+///     delta,
+///     author: "alice".to_string(),
+///     time: time + Duration::from_millis(100 * revision as u64),
+///     revision,
+/// };
+/// let changes = vec![
+///     change(Delta::new().insert("H", None), 1),
+///     change(Delta::new().retain(1, None).insert("i", None), 2),
+///     change(Delta::new().retain(2, None).insert("!", None), 3),
+/// ];
+///
+/// let squashed = squash(&Delta::new(), &changes, Duration::from_secs(1), &[]).unwrap();
+/// assert_eq!(1, squashed.len());
+/// assert_eq!(Delta::new().insert("Hi!", None), squashed[0].delta);
+/// assert_eq!(3, squashed[0].revision);
+///
+/// let squashed = squash(&Delta::new(), &changes, Duration::from_secs(1), &[2]).unwrap();
+/// assert_eq!(vec![2, 3], squashed.iter().map(|c| c.revision).collect::<Vec<_>>());
+/// ```
+pub fn squash(
+    base: &Delta,
+    changes: &[Change],
+    window: Duration,
+    checkpoints: &[usize],
+) -> Result<Vec<Change>, SquashError> {
+    let checkpoints: HashSet<usize> = checkpoints.iter().copied().collect();
+    let mut squashed: Vec<Change> = Vec::new();
+    // Time of the first change of the last squashed change
+    let mut start = SystemTime::UNIX_EPOCH;
+    for change in changes {
+        match squashed.last_mut() {
+            Some(last)
+                if last.author == change.author
+                    && !checkpoints.contains(&last.revision)
+                    && change.time.duration_since(start).unwrap_or_default() < window =>
+            {
+                last.delta = last.delta.compose(&change.delta);
+                last.time = change.time;
+                last.revision = change.revision;
+            }
+            _ => {
+                start = change.time;
+                squashed.push(change.clone());
+            }
+        }
+    }
+    verify(base, changes, &squashed)?;
+    Ok(squashed)
+}
+
+/// Check that the `squashed` log reproduces the document of the `original` log
+///
+/// Applied to the document `base`, each squashed change must result in the same document as
+/// the original changes up to its revision, and both logs must end at the same revision.
+pub fn verify(base: &Delta, original: &[Change], squashed: &[Change]) -> Result<(), SquashError> {
+    let mut originals = original.iter();
+    let mut expected = base.clone();
+    let mut document = base.clone();
+    for change in squashed {
+        let diverged = SquashError::Diverged {
+            revision: change.revision,
+        };
+        document = document.compose(&change.delta);
+        loop {
+            let original = originals.next().ok_or(diverged.clone())?;
+            expected = expected.compose(&original.delta);
+            if original.revision == change.revision {
+                break;
+            }
+        }
+        if document != expected {
+            return Err(diverged);
+        }
+    }
+    match originals.next() {
+        Some(original) => Err(SquashError::Diverged {
+            revision: original.revision,
+        }),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use crate::{AttributesMap, Delta};
+
+    use super::{Change, SquashError, squash, verify};
+
+    fn change(delta: Delta, author: &str, millis: u64, revision: usize) -> Change {
+        Change {
+            delta,
+            author: author.to_string(),
+            time: SystemTime::UNIX_EPOCH + Duration::from_millis(millis),
+            revision,
+        }
+    }
+
+    fn keystrokes() -> Vec<Change> {
+        vec![
+            change(Delta::new().insert("F", None), "alice", 0, 1),
+            change(
+                Delta::new().retain(1, None).insert("o", None),
+                "alice",
+                200,
+                2,
+            ),
+            change(
+                Delta::new().retain(2, None).insert("x", None),
+                "alice",
+                400,
+                3,
+            ),
+            change(Delta::new().delete(1).insert("B", None), "bob", 600, 4),
+            change(
+                Delta::new().retain(3, Some(attributes!("bold" => true))),
+                "bob",
+                800,
+                5,
+            ),
+        ]
+    }
+
+    fn revisions(changes: &[Change]) -> Vec<usize> {
+        changes.iter().map(|change| change.revision).collect()
+    }
+
+    #[test]
+    fn same_author() {
+        let squashed = squash(&Delta::new(), &keystrokes(), Duration::from_secs(1), &[]).unwrap();
+        assert_eq!(vec![3, 5], revisions(&squashed));
+        assert_eq!(Delta::new().insert("Fox", None), squashed[0].delta);
+        assert_eq!(
+            Delta::new()
+                .insert("B", Some(attributes!("bold" => true)))
+                .delete(1)
+                .retain(2, Some(attributes!("bold" => true))),
+            squashed[1].delta
+        );
+        assert_eq!("bob", squashed[1].author);
+        assert_eq!(keystrokes()[4].time, squashed[1].time);
+    }
+
+    #[test]
+    fn window() {
+        let squashed = squash(
+            &Delta::new(),
+            &keystrokes(),
+            Duration::from_millis(300),
+            &[],
+        )
+        .unwrap();
+        assert_eq!(vec![2, 3, 5], revisions(&squashed));
+        let squashed = squash(&Delta::new(), &keystrokes(), Duration::ZERO, &[]).unwrap();
+        assert_eq!(keystrokes(), squashed);
+    }
+
+    #[test]
+    fn checkpoints() {
+        let squashed = squash(
+            &Delta::new(),
+            &keystrokes(),
+            Duration::from_secs(1),
+            &[1, 4],
+        )
+        .unwrap();
+        assert_eq!(vec![1, 3, 4, 5], revisions(&squashed));
+    }
+
+    #[test]
+    fn verify_diverged() {
+        let original = keystrokes();
+        let mut squashed = squash(&Delta::new(), &original, Duration::from_secs(1), &[]).unwrap();
+        assert_eq!(Ok(()), verify(&Delta::new(), &original, &squashed));
+
+        let last = squashed.pop().unwrap();
+        assert_eq!(
+            Err(SquashError::Diverged { revision: 4 }),
+            verify(&Delta::new(), &original, &squashed)
+        );
+        squashed.push(Change {
+            delta: Delta::new().retain(3, Some(attributes!("italic" => true))),
+            ..last
+        });
+        assert_eq!(
+            Err(SquashError::Diverged { revision: 5 }),
+            verify(&Delta::new(), &original, &squashed)
+        );
+    }
+}
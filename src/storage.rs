@@ -0,0 +1,259 @@
+use std::{collections::HashMap, fmt};
+
+use crate::delta::Delta;
+
+/// An error of a [Storage]
+#[derive(Debug)]
+pub enum StorageError {
+    /// The revision is past the current revision of the document
+    UnknownRevision { revision: usize, current: usize },
+    /// The changes up to the revision were compacted into the snapshot
+    Compacted { revision: usize, snapshot: usize },
+    /// A stored [Delta] can't be serialized or deserialized
+    Json(serde_json::Error),
+    /// The SQLite database failed
+    #[cfg(feature = "sqlite")]
+    Sqlite(rusqlite::Error),
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StorageError::UnknownRevision { revision, current } => write!(
+                f,
+                "revision {revision} is past the current revision {current}"
+            ),
+            StorageError::Compacted { revision, snapshot } => write!(
+                f,
+                "revision {revision} is compacted into the snapshot at revision {snapshot}"
+            ),
+            StorageError::Json(error) => write!(f, "invalid stored delta: {error}"),
+            #[cfg(feature = "sqlite")]
+            StorageError::Sqlite(error) => write!(f, "sqlite error: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for StorageError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            StorageError::Json(error) => Some(error),
+            #[cfg(feature = "sqlite")]
+            StorageError::Sqlite(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<serde_json::Error> for StorageError {
+    fn from(error: serde_json::Error) -> Self {
+        StorageError::Json(error)
+    }
+}
+
+/// Persistence of documents as a snapshot and a log of the changes made since
+///
+/// Documents are identified by a string id; a document never written to is empty, at
+/// revision 0. Every change appended to a document increments its revision, and
+/// compacting a document composes its changes into its snapshot, which is then at the
+/// current revision.
+///
+/// # Example
+///
+/// ```
+/// use quill_delta_rs::{Delta, MemoryStorage, Storage};
+///
+/// let mut storage = MemoryStorage::new();
+/// storage.append_change("doc", &Delta::new().insert("Hello\n", None)).unwrap();
+/// storage.append_change("doc", &Delta::new().retain(5, None).insert("!", None)).unwrap();
+///
+/// let hello = Delta::new().insert("Hello!\n", None);
+/// assert_eq!((2, hello.clone()), storage.load_document("doc").unwrap());
+///
+/// storage.compact("doc").unwrap();
+/// assert_eq!((2, hello), storage.load_snapshot("doc").unwrap());
+/// assert!(storage.changes_since("doc", 2).unwrap().is_empty());
+/// ```
+pub trait Storage {
+    /// The snapshot of a document and its revision
+    fn load_snapshot(&self, document: &str) -> Result<(usize, Delta), StorageError>;
+
+    /// Append a change to the log of a document
+    ///
+    /// Returns the new revision of the document.
+    fn append_change(&mut self, document: &str, change: &Delta) -> Result<usize, StorageError>;
+
+    /// The changes made to a document since `revision`, oldest first
+    ///
+    /// Fails if the revision is past the current one or older than the snapshot.
+    fn changes_since(&self, document: &str, revision: usize) -> Result<Vec<Delta>, StorageError>;
+
+    /// Compose the changes of a document into its snapshot and drop them from the log
+    fn compact(&mut self, document: &str) -> Result<(), StorageError>;
+
+    /// The current revision of a document and the document itself
+    ///
+    /// Rebuilt by composing the snapshot with the changes made since.
+    fn load_document(&self, document: &str) -> Result<(usize, Delta), StorageError> {
+        let (revision, snapshot) = self.load_snapshot(document)?;
+        let changes = self.changes_since(document, revision)?;
+        let delta = changes
+            .iter()
+            .fold(snapshot, |delta, change| delta.compose(change));
+        Ok((revision + changes.len(), delta))
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+struct Log {
+    revision: usize,
+    snapshot: Delta,
+    changes: Vec<Delta>,
+}
+
+/// A [Storage] holding documents in memory
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct MemoryStorage {
+    documents: HashMap<String, Log>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        MemoryStorage {
+            documents: HashMap::new(),
+        }
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn load_snapshot(&self, document: &str) -> Result<(usize, Delta), StorageError> {
+        Ok(self
+            .documents
+            .get(document)
+            .map(|log| (log.revision, log.snapshot.clone()))
+            .unwrap_or_default())
+    }
+
+    fn append_change(&mut self, document: &str, change: &Delta) -> Result<usize, StorageError> {
+        let log = self.documents.entry(document.to_string()).or_default();
+        log.changes.push(change.clone());
+        Ok(log.revision + log.changes.len())
+    }
+
+    fn changes_since(&self, document: &str, revision: usize) -> Result<Vec<Delta>, StorageError> {
+        let Some(log) = self.documents.get(document) else {
+            return match revision {
+                0 => Ok(Vec::new()),
+                _ => Err(StorageError::UnknownRevision {
+                    revision,
+                    current: 0,
+                }),
+            };
+        };
+        let current = log.revision + log.changes.len();
+        if revision < log.revision {
+            return Err(StorageError::Compacted {
+                revision,
+                snapshot: log.revision,
+            });
+        }
+        if revision > current {
+            return Err(StorageError::UnknownRevision { revision, current });
+        }
+        Ok(log.changes[revision - log.revision..].to_vec())
+    }
+
+    fn compact(&mut self, document: &str) -> Result<(), StorageError> {
+        if let Some(log) = self.documents.get_mut(document) {
+            for change in log.changes.drain(..) {
+                log.snapshot = log.snapshot.compose(&change);
+                log.revision += 1;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Delta;
+
+    use super::{MemoryStorage, Storage, StorageError};
+
+    fn storage() -> MemoryStorage {
+        let mut storage = MemoryStorage::new();
+        for change in [
+            Delta::new().insert("Hello\n", None),
+            Delta::new().retain(5, None).insert(" World", None),
+            Delta::new().delete(1).insert("J", None),
+        ] {
+            storage.append_change("doc", &change).unwrap();
+        }
+        storage
+    }
+
+    #[test]
+    fn unknown_document() {
+        let storage = MemoryStorage::new();
+        assert_eq!((0, Delta::new()), storage.load_snapshot("doc").unwrap());
+        assert_eq!((0, Delta::new()), storage.load_document("doc").unwrap());
+        assert!(storage.changes_since("doc", 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn append_change() {
+        let mut storage = storage();
+        assert_eq!(
+            4,
+            storage
+                .append_change("doc", &Delta::new().retain(11, None).insert("!", None))
+                .unwrap()
+        );
+        assert_eq!(
+            (4, Delta::new().insert("Jello World!\n", None)),
+            storage.load_document("doc").unwrap()
+        );
+        assert_eq!(1, storage.append_change("other", &Delta::new()).unwrap());
+    }
+
+    #[test]
+    fn changes_since() {
+        let storage = storage();
+        assert_eq!(
+            vec![Delta::new().delete(1).insert("J", None)],
+            storage.changes_since("doc", 2).unwrap()
+        );
+        assert!(storage.changes_since("doc", 3).unwrap().is_empty());
+        assert!(matches!(
+            storage.changes_since("doc", 4),
+            Err(StorageError::UnknownRevision {
+                revision: 4,
+                current: 3
+            })
+        ));
+    }
+
+    #[test]
+    fn compact() {
+        let mut storage = storage();
+        let document = storage.load_document("doc").unwrap();
+        storage.compact("doc").unwrap();
+        assert_eq!(document, storage.load_snapshot("doc").unwrap());
+        assert_eq!(document, storage.load_document("doc").unwrap());
+        assert!(matches!(
+            storage.changes_since("doc", 1),
+            Err(StorageError::Compacted {
+                revision: 1,
+                snapshot: 3
+            })
+        ));
+
+        storage
+            .append_change("doc", &Delta::new().retain(11, None).insert("!", None))
+            .unwrap();
+        assert_eq!(
+            (4, Delta::new().insert("Jello World!\n", None)),
+            storage.load_document("doc").unwrap()
+        );
+    }
+}
@@ -0,0 +1,386 @@
+use std::io::{self, BufRead, BufReader, Read, Write};
+
+use serde::de::Error as _;
+
+use crate::{json::JsonFormat, op::Op};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// Before the opening of the delta
+    Start,
+    /// In the array of ops, after `count` ops, `bare` if not in a delta object
+    Ops { count: usize, bare: bool },
+    /// After the end of the delta, or an error
+    Done,
+}
+
+/// Streaming reader of the [Op]s of a JSON [Delta](crate::Delta)
+///
+/// Reads any [JsonFormat] from any [Read] and yields the ops one at a time, holding a single
+/// op in memory. Other keys of the delta are skipped. Iteration stops after the first error.
+///
+/// # Example
+///
+/// ```
+/// use quill_delta_rs::{Delta, Op, OpReader};
+///
+/// let json = r#"{"ops": [{"insert": "Hello"}, {"insert": "\n", "attributes": {"header": 1}}]}"#;
+/// let mut document = Delta::new();
+/// for op in OpReader::new(json.as_bytes()) {
+///     document.push(op.unwrap());
+/// }
+/// assert_eq!("Hello\n", document.plain_text());
+/// ```
+pub struct OpReader<R: Read> {
+    reader: BufReader<R>,
+    state: State,
+}
+
+impl<R: Read> OpReader<R> {
+    pub fn new(reader: R) -> Self {
+        OpReader {
+            reader: BufReader::new(reader),
+            state: State::Start,
+        }
+    }
+
+    fn peek(&mut self) -> io::Result<Option<u8>> {
+        Ok(self.reader.fill_buf()?.first().copied())
+    }
+
+    /// Skip whitespace and return the next byte without consuming it
+    fn peek_token(&mut self) -> io::Result<Option<u8>> {
+        while let Some(byte) = self.peek()? {
+            if !byte.is_ascii_whitespace() {
+                return Ok(Some(byte));
+            }
+            self.reader.consume(1);
+        }
+        Ok(None)
+    }
+
+    fn expect(&mut self, expected: u8) -> serde_json::Result<()> {
+        match self.peek_token().map_err(serde_json::Error::io)? {
+            Some(byte) if byte == expected => {
+                self.reader.consume(1);
+                Ok(())
+            }
+            Some(byte) => Err(serde_json::Error::custom(format!(
+                "expected `{}`, found `{}`",
+                expected as char, byte as char
+            ))),
+            None => Err(serde_json::Error::io(io::ErrorKind::UnexpectedEof.into())),
+        }
+    }
+
+    /// Read the bytes of the next JSON value, without parsing it
+    fn scan_value(&mut self, value: &mut Vec<u8>) -> serde_json::Result<()> {
+        let eof = || serde_json::Error::io(io::ErrorKind::UnexpectedEof.into());
+        self.peek_token().map_err(serde_json::Error::io)?;
+        let mut depth = 0usize;
+        let mut in_string = false;
+        let mut escaped = false;
+        loop {
+            let Some(byte) = self.peek().map_err(serde_json::Error::io)? else {
+                return if depth == 0 && !in_string && !value.is_empty() {
+                    Ok(())
+                } else {
+                    Err(eof())
+                };
+            };
+            if in_string {
+                value.push(byte);
+                self.reader.consume(1);
+                match byte {
+                    _ if escaped => escaped = false,
+                    b'\\' => escaped = true,
+                    b'"' => {
+                        in_string = false;
+                        if depth == 0 {
+                            return Ok(());
+                        }
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+            match byte {
+                b'{' | b'[' => depth += 1,
+                b'}' | b']' | b',' if depth == 0 => {
+                    return if value.is_empty() {
+                        Err(serde_json::Error::custom(format!(
+                            "expected value, found `{}`",
+                            byte as char
+                        )))
+                    } else {
+                        Ok(())
+                    };
+                }
+                b'}' | b']' => depth -= 1,
+                b'"' => in_string = true,
+                _ if byte.is_ascii_whitespace() && depth == 0 => return Ok(()),
+                _ => {}
+            }
+            value.push(byte);
+            self.reader.consume(1);
+            if depth == 0 && matches!(byte, b'}' | b']') {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Read the next key of the delta object, [None] at its end
+    fn next_key(&mut self, first: bool) -> serde_json::Result<Option<String>> {
+        if self.peek_token().map_err(serde_json::Error::io)? == Some(b'}') {
+            self.reader.consume(1);
+            return Ok(None);
+        }
+        if !first {
+            self.expect(b',')?;
+        }
+        let mut key = Vec::new();
+        self.scan_value(&mut key)?;
+        let key = serde_json::from_slice(&key)?;
+        self.expect(b':')?;
+        Ok(Some(key))
+    }
+
+    /// Skip the keys of the delta object until its ops, returning whether it has any
+    fn find_ops(&mut self, mut first: bool) -> serde_json::Result<bool> {
+        while let Some(key) = self.next_key(first)? {
+            first = false;
+            if key == "ops" || key == "delta" {
+                self.expect(b'[')?;
+                return Ok(true);
+            }
+            self.scan_value(&mut Vec::new())?;
+        }
+        Ok(false)
+    }
+
+    /// Skip the rest of the delta after its ops
+    fn finish(&mut self, bare: bool) -> serde_json::Result<()> {
+        while !bare && self.next_key(false)?.is_some() {
+            self.scan_value(&mut Vec::new())?;
+        }
+        match self.peek_token().map_err(serde_json::Error::io)? {
+            None => Ok(()),
+            Some(_) => Err(serde_json::Error::custom("trailing characters")),
+        }
+    }
+
+    fn read(&mut self) -> serde_json::Result<Option<Op>> {
+        loop {
+            match self.state {
+                State::Start => {
+                    let bare = self.peek_token().map_err(serde_json::Error::io)? == Some(b'[');
+                    if bare {
+                        self.reader.consume(1);
+                    } else {
+                        self.expect(b'{')?;
+                        if !self.find_ops(true)? {
+                            return Err(serde_json::Error::missing_field("delta"));
+                        }
+                    }
+                    self.state = State::Ops { count: 0, bare };
+                }
+                State::Ops { count, bare } => {
+                    if self.peek_token().map_err(serde_json::Error::io)? == Some(b']') {
+                        self.reader.consume(1);
+                        self.finish(bare)?;
+                        self.state = State::Done;
+                        continue;
+                    }
+                    if count > 0 {
+                        self.expect(b',')?;
+                    }
+                    let mut op = Vec::new();
+                    self.scan_value(&mut op)?;
+                    self.state = State::Ops {
+                        count: count + 1,
+                        bare,
+                    };
+                    return serde_json::from_slice(&op).map(Some);
+                }
+                State::Done => return Ok(None),
+            }
+        }
+    }
+}
+
+impl<R: Read> std::iter::Iterator for OpReader<R> {
+    type Item = serde_json::Result<Op>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let op = self.read();
+        if op.is_err() {
+            self.state = State::Done;
+        }
+        op.transpose()
+    }
+}
+
+/// Writer of a JSON [Delta](crate::Delta) one [Op] at a time
+///
+/// Writes the same JSON as serializing the whole [Delta](crate::Delta) in a [JsonFormat],
+/// without holding all its ops in memory. [OpWriter::finish] must be called to close the
+/// JSON.
+///
+/// # Example
+///
+/// ```
+/// use quill_delta_rs::{Delta, Op, OpWriter};
+///
+/// let mut writer = OpWriter::new(Vec::new()).unwrap();
+/// writer.write(&Op::insert("Hello", None)).unwrap();
+/// writer.write(&Op::retain(3, None)).unwrap();
+/// let json = writer.finish().unwrap();
+///
+/// let delta = Delta::new().insert("Hello", None).retain(3, None);
+/// assert_eq!(serde_json::to_vec(&delta).unwrap(), json);
+/// ```
+pub struct OpWriter<W: Write> {
+    writer: W,
+    format: JsonFormat,
+    count: usize,
+}
+
+impl<W: Write> OpWriter<W> {
+    /// Create an [OpWriter] of the default [JsonFormat], writing the opening of the delta to
+    /// `writer`
+    pub fn new(writer: W) -> io::Result<Self> {
+        OpWriter::with_format(writer, JsonFormat::default())
+    }
+
+    /// Create an [OpWriter] of the given [JsonFormat], writing the opening of the delta to
+    /// `writer`
+    pub fn with_format(mut writer: W, format: JsonFormat) -> io::Result<Self> {
+        match format.key() {
+            Some(key) => write!(writer, r#"{{"{key}":["#)?,
+            None => writer.write_all(b"[")?,
+        }
+        Ok(OpWriter {
+            writer,
+            format,
+            count: 0,
+        })
+    }
+
+    /// Write an op
+    pub fn write(&mut self, op: &Op) -> serde_json::Result<()> {
+        if self.count > 0 {
+            self.writer.write_all(b",").map_err(serde_json::Error::io)?;
+        }
+        serde_json::to_writer(&mut self.writer, op)?;
+        self.count += 1;
+        Ok(())
+    }
+
+    /// Close the delta, returning the underlying writer
+    pub fn finish(mut self) -> io::Result<W> {
+        match self.format.key() {
+            Some(_) => self.writer.write_all(b"]}")?,
+            None => self.writer.write_all(b"]")?,
+        }
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{AttributesMap, Delta, Op};
+
+    use crate::JsonFormat;
+
+    use super::{OpReader, OpWriter};
+
+    fn delta() -> Delta {
+        Delta::from(vec![
+            Op::insert("Hello \"[World]\" {", Some(attributes!("bold" => true))),
+            Op::insert(json!({"image": {"src": "fox.png", "size": [1, 2]}}), None),
+            Op::retain(3, Some(attributes!("color" => "#f00"))),
+            Op::delete(2),
+        ])
+    }
+
+    fn read(json: &str) -> serde_json::Result<Vec<Op>> {
+        OpReader::new(json.as_bytes()).collect()
+    }
+
+    #[test]
+    fn read_keys() {
+        let json = serde_json::to_string(&delta()).unwrap();
+        assert_eq!(delta().ops(), &read(&json).unwrap());
+        let json = json.replacen("\"delta\"", "\"ops\"", 1);
+        assert_eq!(delta().ops(), &read(&json).unwrap());
+    }
+
+    #[test]
+    fn read_other_keys() {
+        let json = r#" {
+            "version": {"major": [2, "}"]},
+            "ops" : [ {"delete": 2} , {"retain": 1} ],
+            "author": "alice"
+        } "#;
+        assert_eq!(
+            vec![Op::delete(2), Op::retain(1, None)],
+            read(json).unwrap()
+        );
+        assert!(read(r#"{"ops": []}"#).unwrap().is_empty());
+    }
+
+    #[test]
+    fn read_bare() {
+        let json = serde_json::to_string(&delta().json(JsonFormat::Bare)).unwrap();
+        assert_eq!(delta().ops(), &read(&json).unwrap());
+        assert!(read(" [ ] ").unwrap().is_empty());
+        assert!(read(r#"[{"delete": 2}]}"#).is_err());
+    }
+
+    #[test]
+    fn read_errors() {
+        assert!(read(r#"{"author": "alice"}"#).is_err());
+        assert!(read(r#"{"ops": [{"delete": 2}"#).is_err());
+        assert!(read(r#"{"ops": [{"delete": 2}]} {"#).is_err());
+        assert!(read(r#"{"ops": [{"delete": 2} {"delete": 1}]}"#).is_err());
+
+        let mut reader = OpReader::new(r#"{"ops": [{"delete": 2}, {"remove": 1}]}"#.as_bytes());
+        assert_eq!(Op::delete(2), reader.next().unwrap().unwrap());
+        assert!(reader.next().unwrap().is_err());
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn write() {
+        let mut writer = OpWriter::new(Vec::new()).unwrap();
+        for op in delta().ops() {
+            writer.write(op).unwrap();
+        }
+        let json = writer.finish().unwrap();
+        assert_eq!(delta(), serde_json::from_slice(&json).unwrap());
+        assert_eq!(
+            delta().ops(),
+            &OpReader::new(json.as_slice())
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap()
+        );
+
+        let json = OpWriter::new(Vec::new()).unwrap().finish().unwrap();
+        assert_eq!(Delta::new(), serde_json::from_slice(&json).unwrap());
+    }
+
+    #[test]
+    fn write_formats() {
+        for format in [JsonFormat::Delta, JsonFormat::Ops, JsonFormat::Bare] {
+            let mut writer = OpWriter::with_format(Vec::new(), format).unwrap();
+            for op in delta().ops() {
+                writer.write(op).unwrap();
+            }
+            let json = writer.finish().unwrap();
+            assert_eq!(serde_json::to_vec(&delta().json(format)).unwrap(), json);
+        }
+    }
+}
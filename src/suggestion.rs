@@ -0,0 +1,390 @@
+use serde_json::{Value, json};
+
+use crate::{attributes::AttributesMap, delta::Delta, iter::Iterator, op::Op};
+
+/// Attribute of text suggested for insertion
+pub const SUGGESTION_INSERT: &str = "suggestion-insert";
+/// Attribute of text suggested for deletion
+pub const SUGGESTION_DELETE: &str = "suggestion-delete";
+/// Attribute of text whose formats are suggested for change
+pub const SUGGESTION_FORMAT: &str = "suggestion-format";
+
+/// Whether the op carries the suggestion attribute `key` of the suggestion `id`
+fn is_suggestion(op: &Op, key: &str, id: &str) -> bool {
+    op.attributes()
+        .and_then(|attributes| attributes.get(key))
+        .and_then(|suggestion| suggestion.get("id"))
+        .and_then(Value::as_str)
+        == Some(id)
+}
+
+/// Formats before the format suggestion `id`, or [None] if the op doesn't carry it
+fn formats_before(op: &Op, id: &str) -> Option<AttributesMap> {
+    if !is_suggestion(op, SUGGESTION_FORMAT, id) {
+        return None;
+    }
+    let before = op.attributes()?.get(SUGGESTION_FORMAT)?.get("before")?;
+    serde_json::from_value(before.clone()).ok()
+}
+
+impl Delta {
+    /// Turn a change to this document [Delta] into a suggestion to review
+    ///
+    /// Returns the change to compose onto the document instead of `change`: inserted text
+    /// and embeds get the [SUGGESTION_INSERT] attribute and deleted ones are kept with the
+    /// [SUGGESTION_DELETE] attribute, both set to `{"id": id, "author": author}`. Format
+    /// changes are applied with the [SUGGESTION_FORMAT] attribute, which also holds the
+    /// changed formats `"before"` the suggestion so that rejecting it restores them. Deleting
+    /// or formatting content inserted by the same suggestion applies the change directly.
+    ///
+    /// Content carries a single format suggestion: suggesting a format change of content
+    /// already formatted by another suggestion replaces its [SUGGESTION_FORMAT] attribute.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use quill_delta_rs::{Delta, {attributes, AttributesMap}};
+    /// use serde_json::json;
+    ///
+    /// let document = Delta::new().insert("The red fox\n", None);
+    /// let change = Delta::new().retain(4, None).insert("quick", None).delete(3);
+    /// let suggested = document.compose(&document.suggest(&change, "s1", "alice"));
+    ///
+    /// let mark = json!({"id": "s1", "author": "alice"});
+    /// assert_eq!(
+    ///     Delta::new()
+    ///         .insert("The ", None)
+    ///         .insert("quick", Some(attributes!("suggestion-insert" => mark.clone())))
+    ///         .insert("red", Some(attributes!("suggestion-delete" => mark)))
+    ///         .insert(" fox\n", None),
+    ///     suggested
+    /// );
+    ///
+    /// let accepted = suggested.compose(&suggested.accept_suggestion("s1"));
+    /// assert_eq!(Delta::new().insert("The quick fox\n", None), accepted);
+    /// let rejected = suggested.compose(&suggested.reject_suggestion("s1"));
+    /// assert_eq!(document, rejected);
+    /// ```
+    pub fn suggest(&self, change: &Delta, id: &str, author: &str) -> Delta {
+        let mark = json!({"id": id, "author": author});
+        let mut iter = Iterator::from(self.ops().clone());
+        let mut suggestion = Delta::new();
+        for op in change.ops() {
+            if op.is_insert() {
+                let mut attributes = op.attributes().cloned().unwrap_or_default();
+                attributes.insert(SUGGESTION_INSERT, mark.clone());
+                suggestion.push(Op::insert_any(op.value(), Some(attributes)));
+            } else if op.is_delete() {
+                let mut length = op.len();
+                while length > 0 && iter.has_next() {
+                    let deleted = iter.next_len(length);
+                    length -= deleted.len();
+                    if is_suggestion(&deleted, SUGGESTION_INSERT, id) {
+                        suggestion.push(Op::delete(deleted.len()));
+                    } else {
+                        let mut attributes = AttributesMap::new();
+                        attributes.insert(SUGGESTION_DELETE, mark.clone());
+                        suggestion.push(Op::retain(deleted.len(), Some(attributes)));
+                    }
+                }
+            } else if let Some(format) = op.attributes() {
+                let mut length = op.len();
+                while length > 0 && iter.has_next() {
+                    let formatted = iter.next_len(length);
+                    length -= formatted.len();
+                    let current = formatted.attributes().cloned().unwrap_or_default();
+                    let mut before = AttributesMap::invert(format.clone(), current);
+                    // Formats changed by an earlier change of the same suggestion
+                    if let Some(earlier) = formats_before(&formatted, id) {
+                        before = AttributesMap::compose(before, earlier, true).unwrap_or_default();
+                    }
+                    let mut attributes = format.clone();
+                    if !is_suggestion(&formatted, SUGGESTION_INSERT, id) && !before.is_empty() {
+                        let mut mark = mark.clone();
+                        mark["before"] = json!(before);
+                        attributes.insert(SUGGESTION_FORMAT, mark);
+                    }
+                    suggestion.push(Op::retain(formatted.len(), Some(attributes)));
+                }
+            } else {
+                let mut length = op.len();
+                while length > 0 && iter.has_next() {
+                    length -= iter.next_len(length).len();
+                }
+                suggestion.push(op.clone());
+            }
+        }
+        suggestion.chop();
+        suggestion
+    }
+
+    /// Get the change accepting the suggestion `id` of this document [Delta]
+    ///
+    /// Content suggested for insertion is kept, content suggested for deletion is deleted
+    /// and suggested formats are kept.
+    pub fn accept_suggestion(&self, id: &str) -> Delta {
+        let mut change = Delta::new();
+        for op in self.ops() {
+            if is_suggestion(op, SUGGESTION_DELETE, id) {
+                change.push(Op::delete(op.len()));
+                continue;
+            }
+            let mut attributes = AttributesMap::new();
+            for key in [SUGGESTION_INSERT, SUGGESTION_FORMAT] {
+                if is_suggestion(op, key, id) {
+                    attributes.insert(key, Value::Null);
+                }
+            }
+            change.push(Op::retain(op.len(), Some(attributes)));
+        }
+        change.chop();
+        change
+    }
+
+    /// Get the change rejecting the suggestion `id` of this document [Delta]
+    ///
+    /// Content suggested for insertion is deleted, content suggested for deletion is kept
+    /// and suggested formats are reverted.
+    pub fn reject_suggestion(&self, id: &str) -> Delta {
+        let mut change = Delta::new();
+        for op in self.ops() {
+            if is_suggestion(op, SUGGESTION_INSERT, id) {
+                change.push(Op::delete(op.len()));
+                continue;
+            }
+            let mut attributes = AttributesMap::new();
+            if is_suggestion(op, SUGGESTION_DELETE, id) {
+                attributes.insert(SUGGESTION_DELETE, Value::Null);
+            }
+            if let Some(before) = formats_before(op, id) {
+                attributes = before.into_iter().chain(attributes).collect();
+                attributes.insert(SUGGESTION_FORMAT, Value::Null);
+            }
+            change.push(Op::retain(op.len(), Some(attributes)));
+        }
+        change.chop();
+        change
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Value, json};
+
+    use crate::{AttributesMap, Delta, Op};
+
+    fn mark(id: &str, author: &str) -> Value {
+        json!({"id": id, "author": author})
+    }
+
+    fn document() -> Delta {
+        Delta::new()
+            .insert("The ", None)
+            .insert("quick", Some(attributes!("bold" => true)))
+            .insert(" fox\n", None)
+    }
+
+    #[test]
+    fn suggest_insert() {
+        let change = Delta::new().retain(4, None).insert("very ", None);
+        assert_eq!(
+            Delta::new().retain(4, None).insert(
+                "very ",
+                Some(attributes!("suggestion-insert" => mark("s1", "bob")))
+            ),
+            document().suggest(&change, "s1", "bob")
+        );
+    }
+
+    #[test]
+    fn suggest_delete() {
+        let change = Delta::new().retain(2, None).delete(5);
+        let mark = mark("s1", "bob");
+        assert_eq!(
+            Delta::new()
+                .retain(2, None)
+                .retain(5, Some(attributes!("suggestion-delete" => mark))),
+            document().suggest(&change, "s1", "bob")
+        );
+    }
+
+    #[test]
+    fn delete_own_suggested_insert() {
+        let document = document();
+        let suggested =
+            document.compose(&document.suggest(&Delta::new().insert("A ", None), "s1", "bob"));
+        let change = Delta::new().delete(3);
+        assert_eq!(
+            Delta::new().delete(2).retain(
+                1,
+                Some(attributes!("suggestion-delete" => mark("s1", "bob")))
+            ),
+            suggested.suggest(&change, "s1", "bob")
+        );
+        // Text suggested by another suggestion is marked instead
+        assert_eq!(
+            Delta::new().retain(
+                3,
+                Some(attributes!("suggestion-delete" => mark("s2", "bob")))
+            ),
+            suggested.suggest(&change, "s2", "bob")
+        );
+    }
+
+    #[test]
+    fn embeds() {
+        let document = Delta::from(vec![
+            Op::insert(json!({"image": "fox.png"}), None),
+            Op::insert("\n", None),
+        ]);
+        let width = attributes!("width" => "120");
+        let change = Delta::from(vec![
+            Op::delete(1),
+            Op::insert_any(json!({"image": "cat.png"}), Some(width)),
+        ]);
+        let suggested = document.compose(&document.suggest(&change, "s1", "bob"));
+        assert_eq!(
+            Delta::from(vec![
+                Op::insert_any(
+                    json!({"image": "fox.png"}),
+                    Some(attributes!("suggestion-delete" => mark("s1", "bob")))
+                ),
+                Op::insert_any(
+                    json!({"image": "cat.png"}),
+                    Some(attributes!(
+                        "width" => "120",
+                        "suggestion-insert" => mark("s1", "bob")
+                    ))
+                ),
+                Op::insert("\n", None),
+            ]),
+            suggested
+        );
+        assert_eq!(
+            document.compose(&change),
+            suggested.compose(&suggested.accept_suggestion("s1"))
+        );
+        assert_eq!(
+            document,
+            suggested.compose(&suggested.reject_suggestion("s1"))
+        );
+    }
+
+    #[test]
+    fn suggest_format() {
+        let document = document();
+        let change = Delta::new().retain(4, None).retain(
+            5,
+            Some(attributes!("bold" => Value::Null, "italic" => true)),
+        );
+        let suggestion = document.suggest(&change, "s1", "bob");
+        let mut format = mark("s1", "bob");
+        format["before"] = json!({"bold": true, "italic": null});
+        assert_eq!(
+            Delta::new().retain(4, None).retain(
+                5,
+                Some(attributes!(
+                    "bold" => Value::Null,
+                    "italic" => true,
+                    "suggestion-format" => format
+                ))
+            ),
+            suggestion
+        );
+        let suggested = document.compose(&suggestion);
+        assert_eq!(
+            document.compose(&change),
+            suggested.compose(&suggested.accept_suggestion("s1"))
+        );
+        assert_eq!(
+            document,
+            suggested.compose(&suggested.reject_suggestion("s1"))
+        );
+
+        // Rejecting restores the formats before the first change of the suggestion
+        let change = Delta::new()
+            .retain(4, None)
+            .retain(5, Some(attributes!("italic" => Value::Null)));
+        let suggested = suggested.compose(&suggested.suggest(&change, "s1", "bob"));
+        assert_eq!(
+            document,
+            suggested.compose(&suggested.reject_suggestion("s1"))
+        );
+        assert_eq!(
+            Delta::new().insert("The quick fox\n", None),
+            suggested.compose(&suggested.accept_suggestion("s1"))
+        );
+    }
+
+    #[test]
+    fn format_after_long_retain() {
+        let document = document();
+        let change = Delta::new()
+            .retain(10, None)
+            .retain(3, Some(attributes!("bold" => true)));
+        let suggestion = document.suggest(&change, "s1", "bob");
+        let mut format = mark("s1", "bob");
+        format["before"] = json!({"bold": null});
+        assert_eq!(
+            Delta::new().retain(10, None).retain(
+                3,
+                Some(attributes!("bold" => true, "suggestion-format" => format))
+            ),
+            suggestion
+        );
+        let suggested = document.compose(&suggestion);
+        assert_eq!(
+            document,
+            suggested.compose(&suggested.reject_suggestion("s1"))
+        );
+    }
+
+    #[test]
+    fn format_own_suggested_insert() {
+        let document = document();
+        let suggested =
+            document.compose(&document.suggest(&Delta::new().insert("A ", None), "s1", "bob"));
+        let change = Delta::new().retain(2, Some(attributes!("bold" => true)));
+        assert_eq!(change, suggested.suggest(&change, "s1", "bob"));
+    }
+
+    #[test]
+    fn accept_and_reject() {
+        let document = document();
+        let first = Delta::new().retain(4, None).delete(6);
+        let document = document.compose(&document.suggest(&first, "s1", "alice"));
+        let second = Delta::new().retain(13, None).insert("!", None);
+        let document = document.compose(&document.suggest(&second, "s2", "bob"));
+
+        let accepted = document.compose(&document.accept_suggestion("s1"));
+        assert_eq!(
+            Delta::from(vec![
+                Op::insert("The fox", None),
+                Op::insert(
+                    "!",
+                    Some(attributes!("suggestion-insert" => mark("s2", "bob")))
+                ),
+                Op::insert("\n", None),
+            ]),
+            accepted
+        );
+        let rejected = accepted.compose(&accepted.reject_suggestion("s2"));
+        assert_eq!(Delta::new().insert("The fox\n", None), rejected);
+
+        let rejected = document.compose(&document.reject_suggestion("s1"));
+        let accepted = rejected.compose(&rejected.accept_suggestion("s2"));
+        assert_eq!(
+            Delta::new()
+                .insert("The ", None)
+                .insert("quick", Some(attributes!("bold" => true)))
+                .insert(" fox!\n", None),
+            accepted
+        );
+    }
+
+    #[test]
+    fn unknown_suggestion() {
+        assert_eq!(Delta::new(), document().accept_suggestion("s1"));
+        assert_eq!(Delta::new(), document().reject_suggestion("s1"));
+    }
+}
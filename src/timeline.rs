@@ -0,0 +1,185 @@
+use crate::delta::Delta;
+
+/// Log of the changes made to a document, to get the document at any revision
+///
+/// Snapshots of the document are kept every `interval` revisions, along with the inverse of
+/// every change. [Timeline::document_at] starts from the nearest snapshot, or the current
+/// document, and composes either the changes after it or the inverses before it, whichever
+/// takes fewer steps.
+///
+/// # Example
+///
+/// ```
+/// use quill_delta_rs::{Delta, MemoryStorage, Storage, Timeline};
+///
+/// let mut storage = MemoryStorage::new();
+/// storage.append_change("doc", &Delta::new().insert("Hello\n", None)).unwrap();
+/// storage.append_change("doc", &Delta::new().retain(5, None).insert(" World", None)).unwrap();
+/// storage.append_change("doc", &Delta::new().delete(1).insert("J", None)).unwrap();
+///
+/// let (revision, snapshot) = storage.load_snapshot("doc").unwrap();
+/// let mut timeline = Timeline::new(revision, snapshot).interval(2);
+/// for change in storage.changes_since("doc", revision).unwrap() {
+///     timeline.push(change);
+/// }
+///
+/// assert_eq!(3, timeline.revision());
+/// assert_eq!(Some(Delta::new().insert("Hello World\n", None)), timeline.document_at(2));
+/// assert_eq!(None, timeline.document_at(4));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Timeline {
+    interval: usize,
+    /// Revision of the first snapshot
+    base: usize,
+    /// Revisions and documents of the snapshots, oldest first
+    snapshots: Vec<(usize, Delta)>,
+    /// Change from revision `base + i` to the next one, at index `i`
+    changes: Vec<Delta>,
+    /// Inverse of the change at the same index
+    inverses: Vec<Delta>,
+    document: Delta,
+}
+
+impl Timeline {
+    /// Create a [Timeline] of the document `snapshot` at `revision`, with a snapshot every
+    /// 100 revisions
+    pub fn new(revision: usize, snapshot: Delta) -> Self {
+        Timeline {
+            interval: 100,
+            base: revision,
+            snapshots: vec![(revision, snapshot.clone())],
+            changes: Vec::new(),
+            inverses: Vec::new(),
+            document: snapshot,
+        }
+    }
+
+    /// Set the number of revisions between snapshots
+    ///
+    /// # Panics
+    ///
+    /// If the interval is 0
+    pub fn interval(mut self, interval: usize) -> Self {
+        assert!(interval > 0, "interval should be greater than 0");
+        self.interval = interval;
+        self
+    }
+
+    /// The current revision
+    pub fn revision(&self) -> usize {
+        self.base + self.changes.len()
+    }
+
+    /// The current document
+    pub fn document(&self) -> &Delta {
+        &self.document
+    }
+
+    /// Apply a change to the current document
+    ///
+    /// Returns the new revision.
+    pub fn push(&mut self, change: Delta) -> usize {
+        self.inverses.push(change.invert(&self.document));
+        self.document = self.document.compose(&change);
+        self.changes.push(change);
+        let revision = self.revision();
+        let (last, _) = self.snapshots.last().unwrap();
+        if revision - last >= self.interval {
+            self.snapshots.push((revision, self.document.clone()));
+        }
+        revision
+    }
+
+    /// The document at `revision`
+    ///
+    /// Returns [None] if the revision is before the first snapshot or after the current one.
+    pub fn document_at(&self, revision: usize) -> Option<Delta> {
+        if revision < self.base || revision > self.revision() {
+            return None;
+        }
+        let next = self
+            .snapshots
+            .partition_point(|(snapshot, _)| *snapshot <= revision);
+        let (before, document) = &self.snapshots[next - 1];
+        let (after, later) = match self.snapshots.get(next) {
+            Some((after, document)) => (*after, document),
+            None => (self.revision(), &self.document),
+        };
+
+        if revision - before <= after - revision {
+            let changes = &self.changes[before - self.base..revision - self.base];
+            Some(changes.iter().fold(document.clone(), |document, change| {
+                document.compose(change)
+            }))
+        } else {
+            let inverses = &self.inverses[revision - self.base..after - self.base];
+            Some(
+                inverses
+                    .iter()
+                    .rev()
+                    .fold(later.clone(), |document, inverse| document.compose(inverse)),
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{AttributesMap, Delta};
+
+    use super::Timeline;
+
+    fn changes() -> Vec<Delta> {
+        vec![
+            Delta::new().insert("Hello", None),
+            Delta::new().retain(5, None).insert(" World", None),
+            Delta::new().retain(5, Some(attributes!("bold" => true))),
+            Delta::new().delete(1).insert("J", None),
+            Delta::new().retain(5, None).delete(6),
+            Delta::new().retain(5, None).insert("y!", None),
+            Delta::new().retain(1, Some(attributes!("bold" => serde_json::Value::Null))),
+        ]
+    }
+
+    fn documents() -> Vec<Delta> {
+        let mut documents = vec![Delta::new()];
+        for change in changes() {
+            documents.push(documents.last().unwrap().compose(&change));
+        }
+        documents
+    }
+
+    #[test]
+    fn document_at() {
+        let expected = documents();
+        for interval in 1..=8 {
+            let mut timeline = Timeline::new(0, Delta::new()).interval(interval);
+            for (revision, change) in changes().into_iter().enumerate() {
+                assert_eq!(revision + 1, timeline.push(change));
+            }
+            for (revision, document) in expected.iter().enumerate() {
+                assert_eq!(Some(document), timeline.document_at(revision).as_ref());
+            }
+            assert_eq!(expected.last().unwrap(), timeline.document());
+        }
+    }
+
+    #[test]
+    fn from_snapshot() {
+        let expected = documents();
+        let mut timeline = Timeline::new(10, expected[3].clone()).interval(2);
+        for change in changes().into_iter().skip(3) {
+            timeline.push(change);
+        }
+        assert_eq!(14, timeline.revision());
+        assert_eq!(None, timeline.document_at(9));
+        assert_eq!(None, timeline.document_at(15));
+        for revision in 10..=14 {
+            assert_eq!(
+                Some(&expected[revision - 7]),
+                timeline.document_at(revision).as_ref()
+            );
+        }
+    }
+}
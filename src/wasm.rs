@@ -0,0 +1,194 @@
+use std::{cell::RefCell, rc::Rc};
+
+use serde::Serialize;
+use serde_json::Value;
+use wasm_bindgen::prelude::*;
+
+use crate::{attributes::AttributesMap, delta::Delta, json::JsonFormat, op::Op};
+
+fn to_js<T: Serialize + ?Sized>(value: &T) -> Result<JsValue, JsError> {
+    // Attributes and embeds as plain objects rather than `Map`s
+    let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+    Ok(value.serialize(&serializer)?)
+}
+
+fn attributes(value: JsValue) -> Result<Option<AttributesMap>, JsError> {
+    if value.is_undefined() || value.is_null() {
+        return Ok(None);
+    }
+    Ok(Some(serde_wasm_bindgen::from_value(value)?))
+}
+
+/// [Delta] exposed to JavaScript as `Delta`, available with the `wasm` feature
+///
+/// Mirrors the API of quill-delta: it's created from an array of ops or an object with an
+/// `ops` key, and `ops` and `toJSON` return plain JavaScript objects of the same shape.
+/// `insert`, `retain` and `delete` modify the delta and return it for chaining: the
+/// returned object and the original one share the same ops, as do Rust clones.
+///
+/// Lengths count characters, i.e. Unicode scalar values, where quill-delta counts UTF-16
+/// code units: they differ for characters outside the Basic Multilingual Plane, such as
+/// most emojis.
+#[wasm_bindgen(js_name = Delta)]
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct WasmDelta {
+    delta: Rc<RefCell<Delta>>,
+}
+
+#[wasm_bindgen(js_class = Delta)]
+impl WasmDelta {
+    /// `new Delta(ops)`, `ops` being an array of ops, `{ops: [...]}` or `{delta: [...]}`
+    #[wasm_bindgen(constructor)]
+    pub fn new(ops: JsValue) -> Result<WasmDelta, JsError> {
+        if ops.is_undefined() || ops.is_null() {
+            return Ok(WasmDelta::default());
+        }
+        let delta: Delta = serde_wasm_bindgen::from_value(ops)?;
+        Ok(WasmDelta::from(delta))
+    }
+
+    /// Parse a JSON string in any [JsonFormat]
+    pub fn parse(json: &str) -> Result<WasmDelta, JsError> {
+        let delta: Delta = serde_json::from_str(json)?;
+        Ok(WasmDelta::from(delta))
+    }
+
+    /// Serialize as a JSON string, `{"ops": [...]}` as quill-delta
+    pub fn stringify(&self) -> Result<String, JsError> {
+        Ok(serde_json::to_string(
+            &self.delta.borrow().json(JsonFormat::Ops),
+        )?)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn ops(&self) -> Result<JsValue, JsError> {
+        to_js(self.delta.borrow().ops())
+    }
+
+    /// `{ops: [...]}`, used by `JSON.stringify`
+    #[wasm_bindgen(js_name = toJSON)]
+    pub fn to_json(&self) -> Result<JsValue, JsError> {
+        to_js(&self.delta.borrow().json(JsonFormat::Ops))
+    }
+
+    /// Insert text or an embed, both with attributes, ignoring empty text
+    pub fn insert(&self, value: JsValue, attributes: JsValue) -> Result<WasmDelta, JsError> {
+        let value: Value = serde_wasm_bindgen::from_value(value)?;
+        if value.is_null() || value.as_str() == Some("") {
+            return Ok(self.clone());
+        }
+        let op = Op::insert_any(value, self::attributes(attributes)?);
+        self.delta.borrow_mut().push(op);
+        Ok(self.clone())
+    }
+
+    /// Retain `length` characters, ignoring a length of 0
+    pub fn retain(&self, length: usize, attributes: JsValue) -> Result<WasmDelta, JsError> {
+        if length > 0 {
+            let op = Op::retain(length, self::attributes(attributes)?);
+            self.delta.borrow_mut().push(op);
+        }
+        Ok(self.clone())
+    }
+
+    /// Delete `length` characters, ignoring a length of 0
+    pub fn delete(&self, length: usize) -> WasmDelta {
+        if length > 0 {
+            self.delta.borrow_mut().push(Op::delete(length));
+        }
+        self.clone()
+    }
+
+    pub fn compose(&self, other: &WasmDelta) -> WasmDelta {
+        WasmDelta::from(self.delta.borrow().compose(&other.delta.borrow()))
+    }
+
+    pub fn invert(&self, base: &WasmDelta) -> WasmDelta {
+        WasmDelta::from(self.delta.borrow().invert(&base.delta.borrow()))
+    }
+
+    pub fn slice(&self, start: Option<usize>, end: Option<usize>) -> WasmDelta {
+        WasmDelta::from(self.delta.borrow().slice(start.unwrap_or_default(), end))
+    }
+
+    /// Length of the ops in characters, see [WasmDelta] for the difference with quill-delta
+    pub fn length(&self) -> usize {
+        self.delta.borrow().len()
+    }
+
+    /// Change of the document length in characters, negative if more is deleted than
+    /// inserted, see [WasmDelta] for the difference with quill-delta
+    #[wasm_bindgen(js_name = changeLength)]
+    pub fn change_length(&self) -> f64 {
+        // A JavaScript number rather than a BigInt, as quill-delta
+        self.delta.borrow().ops().iter().fold(0.0, |length, op| {
+            match (op.is_insert(), op.is_delete()) {
+                (true, _) => length + op.len() as f64,
+                (_, true) => length - op.len() as f64,
+                _ => length,
+            }
+        })
+    }
+}
+
+impl From<Delta> for WasmDelta {
+    fn from(delta: Delta) -> Self {
+        WasmDelta {
+            delta: Rc::new(RefCell::new(delta)),
+        }
+    }
+}
+
+impl From<WasmDelta> for Delta {
+    fn from(delta: WasmDelta) -> Self {
+        Rc::try_unwrap(delta.delta)
+            .map(RefCell::into_inner)
+            .unwrap_or_else(|delta| delta.borrow().clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Delta;
+
+    use super::WasmDelta;
+
+    // JsValues can only be created in WebAssembly, only the pure Rust methods are tested
+    #[test]
+    fn methods() {
+        let document = WasmDelta::from(Delta::new().insert("Hello", None));
+        let change = WasmDelta::from(Delta::new().retain(5, None).insert(" World", None));
+        let composed = document.compose(&change);
+        assert_eq!(
+            Delta::new().insert("Hello World", None),
+            Delta::from(composed.clone())
+        );
+        assert_eq!(11, composed.length());
+        assert_eq!(6.0, change.change_length());
+        let deleting = WasmDelta::from(Delta::new().retain(2, None).delete(3).insert("a", None));
+        assert_eq!(-2.0, deleting.change_length());
+        assert_eq!(document, composed.compose(&change.invert(&document)));
+        assert_eq!(
+            Delta::new().insert("World", None),
+            composed.slice(Some(6), None).into()
+        );
+        assert_eq!(composed, composed.slice(None, None));
+    }
+
+    #[test]
+    fn delete() {
+        let delta = WasmDelta::default();
+        assert_eq!(WasmDelta::default(), delta.delete(0));
+        assert_eq!(Delta::new().delete(3), Delta::from(delta.delete(3)));
+        assert_eq!(Delta::new().delete(3), delta.into());
+    }
+
+    #[test]
+    fn chaining_shares_ops() {
+        let delta = WasmDelta::default();
+        let chained = delta.delete(3).delete(2);
+        assert_eq!(Delta::new().delete(5), Delta::from(delta.clone()));
+        delta.delete(1);
+        assert_eq!(Delta::new().delete(6), chained.into());
+    }
+}